@@ -0,0 +1,101 @@
+use serenity::{
+    client::Context,
+    model::{id::ChannelId, user::User},
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{Challenge, ScoreAdjustment, Setting},
+        Database,
+    },
+    InteractionError, InteractionResult,
+};
+
+/// Titles and colours of the announcements for the first three solves of a challenge.
+const BLOODS: [(&str, (u8, u8, u8)); 3] = [
+    ("🩸 First blood!", (220, 20, 60)),
+    ("🥈 Second blood!", (192, 192, 192)),
+    ("🥉 Third blood!", (205, 127, 50)),
+];
+
+/// Gets the title of the `place`th solve of a challenge (starting from 1), if it is one of the
+/// first three.
+pub fn blood_title(place: i64) -> Option<&'static str> {
+    BLOODS.get((place - 1) as usize).map(|(title, _)| *title)
+}
+
+/// Adds the configured bonus for being the `place`th to solve `challenge` to the score of
+/// `user_id`, returning the bonus.
+pub async fn award_blood_bonus(
+    ctx: &Context,
+    db: &Database,
+    user_id: u64,
+    challenge: &Challenge,
+    place: i64,
+) -> Result<i64, InteractionError> {
+    let bonus = match (Setting::get(db, Setting::BLOOD_BONUS)?, blood_title(place)) {
+        (Some(bonuses), Some(_)) => bonuses
+            .split(',')
+            .nth((place - 1) as usize)
+            .and_then(|bonus| bonus.parse().ok())
+            .unwrap_or(0),
+        _ => 0,
+    };
+
+    if bonus > 0 {
+        // The bonus goes through the adjustment ledger so admins can see and undo it
+        let bot_id = ctx.http.get_current_user().await?.id.0;
+
+        ScoreAdjustment::create(
+            db,
+            user_id,
+            bonus,
+            &format!("{} on {}", blood_title(place).unwrap(), challenge.name),
+            bot_id,
+        )?;
+    }
+
+    Ok(bonus)
+}
+
+/// Announces that `user` solved `challenge` in the solves channel, if there is one.
+pub async fn announce_solve(
+    ctx: &Context,
+    db: &Database,
+    user: &User,
+    challenge: &Challenge,
+    place: i64,
+    bonus: i64,
+) -> InteractionResult {
+    let channel_id = match Setting::get_id(db, Setting::SOLVES_CHANNEL)? {
+        Some(channel_id) => channel_id,
+        None => return Ok(()),
+    };
+
+    let (title, colour) = BLOODS
+        .get((place - 1) as usize)
+        .copied()
+        .unwrap_or(("Challenge solved!", (0, 255, 0)));
+
+    ChannelId(channel_id)
+        .send_message(&ctx.http, |message| {
+            message.embed(|embed| {
+                embed.title(title).colour(colour).description(format!(
+                    "{} solved **{}** ({} points)",
+                    user.mention(),
+                    challenge.name,
+                    challenge.points
+                ));
+
+                if bonus > 0 {
+                    embed.field("Bonus", format!("+{} points", bonus), true);
+                }
+
+                embed
+            })
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    client::Context,
+    model::interactions::{
+        application_command::ApplicationCommandInteraction,
+        message_component::{ButtonStyle, MessageComponentInteraction},
+        InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+    },
+};
+
+use crate::{
+    challenges::flag_modal,
+    db::{
+        models::{Challenge, ChallengeStage, Solve, StageSolve},
+        Database,
+    },
+    truncate, InteractionError, InteractionResult,
+};
+
+pub const ID_SELECT_CHALLENGE: &str = "select_challenge";
+
+/// Followed by `:<page>`
+pub const ID_BUTTON_CHALLENGES_PAGE: &str = "button_challenges_page";
+
+/// Followed by `:<challenge id>`
+pub const ID_BUTTON_FLAG_SUBMIT: &str = "button_flag_submit";
+
+/// How many challenges each page of the select menu offers, the most Discord allows.
+const CHALLENGES_PER_PAGE: usize = 25;
+
+pub async fn cmd_challenges(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let challenges = Challenge::get_all(db)?;
+    let description = challenges_description(db, &challenges, command.user.id.0)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| challenges_embed(embed, description))
+                        .components(|components| challenges_menu(components, &challenges, 0))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn button_challenges_page_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    page: &str,
+) -> InteractionResult {
+    let page = page
+        .parse::<usize>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    let challenges = Challenge::get_all(db)?;
+    let description = challenges_description(db, &challenges, interaction.user.id.0)?;
+
+    // Challenges may have been archived since the buttons were made
+    let page = page.min(page_count(&challenges) - 1);
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.embed(|embed| challenges_embed(embed, description))
+                        .components(|components| challenges_menu(components, &challenges, page))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Lists every challenge by category, marking those `user_id` has solved.
+fn challenges_description(
+    db: &Database,
+    challenges: &[Challenge],
+    user_id: u64,
+) -> Result<String, InteractionError> {
+    if challenges.is_empty() {
+        return Ok("There are no challenges yet!".to_string());
+    }
+
+    let solve_counts = Solve::get_counts(db)?;
+    let solved: Vec<i64> = Solve::get_by_user(db, user_id)?
+        .iter()
+        .map(|s| s.challenge_id)
+        .collect();
+
+    // Challenges are ordered by category, so each category's challenges are contiguous
+    let mut description = String::new();
+    let mut category = None;
+    for challenge in challenges {
+        if category != Some(&challenge.category) {
+            description.push_str(&format!("\n**__{}__**\n", challenge.category));
+            category = Some(&challenge.category);
+        }
+
+        description.push_str(&format!(
+            "{} **{}** - {} points, {} solves\n",
+            if solved.contains(&challenge.id) {
+                "✅"
+            } else {
+                "⬜"
+            },
+            challenge.name,
+            challenge.points,
+            solve_counts.get(&challenge.id).unwrap_or(&0)
+        ));
+    }
+
+    Ok(description)
+}
+
+fn challenges_embed(embed: &mut CreateEmbed, description: String) -> &mut CreateEmbed {
+    embed
+        .title("Challenges")
+        .colour((0, 128, 255))
+        .description(truncate(&description, 4096))
+}
+
+fn page_count(challenges: &[Challenge]) -> usize {
+    challenges.len().div_ceil(CHALLENGES_PER_PAGE).max(1)
+}
+
+/// Builds the select menu of one page of challenges, with buttons to turn the page if there are
+/// more than fit in one menu.
+fn challenges_menu<'a>(
+    components: &'a mut CreateComponents,
+    challenges: &[Challenge],
+    page: usize,
+) -> &'a mut CreateComponents {
+    if challenges.is_empty() {
+        return components;
+    }
+
+    let page_count = page_count(challenges);
+
+    components.create_action_row(|action_row| {
+        action_row.create_select_menu(|menu| {
+            menu.custom_id(ID_SELECT_CHALLENGE)
+                .placeholder(if page_count > 1 {
+                    format!("View a challenge (page {}/{})", page + 1, page_count)
+                } else {
+                    "View a challenge".to_string()
+                })
+                .options(|options| {
+                    for challenge in challenges
+                        .iter()
+                        .skip(page * CHALLENGES_PER_PAGE)
+                        .take(CHALLENGES_PER_PAGE)
+                    {
+                        options.create_option(|option| {
+                            option
+                                .label(&challenge.name)
+                                .value(challenge.id)
+                                .description(&challenge.category)
+                        });
+                    }
+                    options
+                })
+        })
+    });
+
+    if page_count > 1 {
+        components.create_action_row(|action_row| {
+            action_row
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Secondary)
+                        .label("Previous")
+                        .custom_id(format!(
+                            "{}:{}",
+                            ID_BUTTON_CHALLENGES_PAGE,
+                            page.saturating_sub(1)
+                        ))
+                        .disabled(page == 0)
+                })
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Secondary)
+                        .label("Next")
+                        .custom_id(format!("{}:{}", ID_BUTTON_CHALLENGES_PAGE, page + 1))
+                        .disabled(page + 1 >= page_count)
+                })
+        });
+    }
+
+    components
+}
+
+pub async fn select_challenge_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+) -> InteractionResult {
+    let challenge_id = interaction
+        .data
+        .values
+        .get(0)
+        .and_then(|v| v.parse::<i64>().ok())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let challenge = match Challenge::get_by_id(db, challenge_id)? {
+        Some(challenge) if !challenge.archived => challenge,
+        _ => {
+            interaction
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                                .content("That challenge no longer exists!")
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let solve_count = *Solve::get_counts(db)?.get(&challenge.id).unwrap_or(&0);
+    let solve = Solve::get_by_user(db, interaction.user.id.0)?
+        .into_iter()
+        .find(|s| s.challenge_id == challenge.id);
+
+    let stages = ChallengeStage::get_by_challenge(db, challenge.id)?;
+    let solved_stage_ids =
+        StageSolve::get_solved_stage_ids(db, interaction.user.id.0, challenge.id)?;
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| {
+                            embed
+                                .title(&challenge.name)
+                                .colour((0, 128, 255))
+                                .description(&challenge.description)
+                                .field("Category", &challenge.category, true)
+                                .field("Points", challenge.points, true)
+                                .field("Solves", solve_count, true);
+
+                            if stages.len() > 1 {
+                                embed.field(
+                                    format!(
+                                        "Stages ({}/{} complete)",
+                                        solved_stage_ids.len(),
+                                        stages.len()
+                                    ),
+                                    stages
+                                        .iter()
+                                        .map(|stage| {
+                                            format!(
+                                                "{} {} - {} points",
+                                                if solved_stage_ids.contains(&stage.id) {
+                                                    "✅"
+                                                } else {
+                                                    "⬜"
+                                                },
+                                                stage.name,
+                                                stage.points
+                                            )
+                                        })
+                                        .collect::<Vec<_>>()
+                                        .join("\n"),
+                                    false,
+                                );
+                            }
+
+                            if let Some(solve) = &solve {
+                                embed.field(
+                                    "Status",
+                                    format!("✅ Solved <t:{}:R>", solve.solved_at),
+                                    false,
+                                );
+                            }
+
+                            embed
+                        })
+                        .components(|components| {
+                            components.create_action_row(|action_row| {
+                                action_row.create_button(|button| {
+                                    button
+                                        .style(ButtonStyle::Primary)
+                                        .label("Submit flag")
+                                        .custom_id(format!(
+                                            "{}:{}",
+                                            ID_BUTTON_FLAG_SUBMIT, challenge.id
+                                        ))
+                                        .disabled(solve.is_some())
+                                })
+                            })
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn button_flag_submit_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    challenge_id: &str,
+) -> InteractionResult {
+    let challenge_id = challenge_id
+        .parse::<i64>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    let challenge =
+        Challenge::get_by_id(db, challenge_id)?.ok_or(InteractionError::UnprocessableRequest)?;
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|data| flag_modal(data, Some(&challenge)))
+        })
+        .await?;
+
+    Ok(())
+}
@@ -1,22 +1,29 @@
 use serenity::{
+    builder::CreateInteractionResponseData,
     client::Context,
     model::{
-        id::RoleId,
+        id::{ChannelId, RoleId},
         interactions::{
-            application_command::ApplicationCommandInteraction,
+            application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType},
+            autocomplete::AutocompleteInteraction,
             message_component::{ActionRowComponent, InputTextStyle},
             modal::ModalSubmitInteraction,
-            InteractionResponseType,
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
         },
     },
     prelude::Mentionable,
 };
 
 use crate::{
+    announcements,
     db::{
-        models::{self, Challenge},
+        models::{
+            AuditLog, Challenge, ChallengeStage, DecoyFlag, KnownWrongAnswer, Sanction, Setting,
+            Solve, StageSolve, Submission,
+        },
         Database,
     },
+    decoys, discussions, get_input_value, get_option, response_flags, rewards, truncate,
     InteractionError, InteractionResult,
 };
 
@@ -26,40 +33,104 @@ pub const ID_INPUT_MODAL_FLAG_SUBMIT: &str = "modal_flag_submit_input";
 pub const ID_MODAL_CHAL_ADD: &str = "modal_chal_add";
 pub const ID_INPUT_CHAL_MODAL_CHAL_ADD: &str = "modal_chal_add_input_chal";
 pub const ID_INPUT_FLAG_MODAL_CHAL_ADD: &str = "modal_chal_add_input_flag";
+pub const ID_INPUT_POINTS_MODAL_CHAL_ADD: &str = "modal_chal_add_input_points";
+pub const ID_INPUT_CATEGORY_MODAL_CHAL_ADD: &str = "modal_chal_add_input_category";
+pub const ID_INPUT_DESCRIPTION_MODAL_CHAL_ADD: &str = "modal_chal_add_input_description";
+
+/// Followed by `:<challenge id>`
+pub const ID_MODAL_CHAL_EDIT: &str = "modal_chal_edit";
+pub const ID_INPUT_NAME_MODAL_CHAL_EDIT: &str = "modal_chal_edit_input_name";
+pub const ID_INPUT_CATEGORY_MODAL_CHAL_EDIT: &str = "modal_chal_edit_input_category";
+pub const ID_INPUT_DESCRIPTION_MODAL_CHAL_EDIT: &str = "modal_chal_edit_input_description";
+
+/// Followed by `:<stage id>:<grace period in minutes>`
+pub const ID_MODAL_FLAG_ROTATE: &str = "modal_flag_rotate";
+pub const ID_INPUT_FLAG_MODAL_FLAG_ROTATE: &str = "modal_flag_rotate_input_flag";
+
+/// How long a rotated flag is still accepted for, unless specified otherwise.
+const DEFAULT_FLAG_GRACE_MINUTES: i64 = 10;
+
+/// Followed by `:<challenge id>`
+pub const ID_MODAL_STAGE_ADD: &str = "modal_stage_add";
+pub const ID_INPUT_NAME_MODAL_STAGE_ADD: &str = "modal_stage_add_input_name";
+pub const ID_INPUT_FLAG_MODAL_STAGE_ADD: &str = "modal_stage_add_input_flag";
+pub const ID_INPUT_POINTS_MODAL_STAGE_ADD: &str = "modal_stage_add_input_points";
 
 pub async fn cmd_submitflag(
     ctx: Context,
+    db: &Database,
     command: ApplicationCommandInteraction,
 ) -> InteractionResult {
+    let challenge_id = get_option(&command.data.options, "challenge")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64());
+
+    let challenge = match challenge_id {
+        Some(challenge_id) => match Challenge::get_by_id(db, challenge_id)? {
+            Some(challenge) if !challenge.archived => Some(challenge),
+            _ => {
+                command
+                    .create_interaction_response(&ctx.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|data| {
+                                data.flags(
+                                    InteractionApplicationCommandCallbackDataFlags::EPHEMERAL,
+                                )
+                                .content("No challenge with that id!")
+                            })
+                    })
+                    .await?;
+
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
     command
         .create_interaction_response(&ctx.http, |response| {
             response
                 .kind(InteractionResponseType::Modal)
-                .interaction_response_data(|message| {
-                    message
-                        .custom_id(ID_MODAL_FLAG_SUBMIT)
-                        .title("Submit flag")
-                        .components(|components| {
-                            components.create_action_row(|action| {
-                                action.create_input_text(|input| {
-                                    input
-                                        .custom_id(ID_INPUT_MODAL_FLAG_SUBMIT)
-                                        .style(InputTextStyle::Short)
-                                        .label("Submit flag here: ")
-                                        .required(true)
-                                        .min_length(1)
-                                        .max_length(100)
-                                        .value("")
-                                })
-                            })
-                        })
-                })
+                .interaction_response_data(|data| flag_modal(data, challenge.as_ref()))
         })
         .await?;
 
     Ok(())
 }
 
+/// Builds the modal for submitting a flag, bound to `challenge` if given so the flag is only
+/// checked against it.
+pub fn flag_modal<'a, 'b>(
+    data: &'b mut CreateInteractionResponseData<'a>,
+    challenge: Option<&Challenge>,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    let (custom_id, title) = match challenge {
+        Some(challenge) => (
+            format!("{}:{}", ID_MODAL_FLAG_SUBMIT, challenge.id),
+            format!("Submit flag for {}", truncate(&challenge.name, 29)),
+        ),
+        None => (ID_MODAL_FLAG_SUBMIT.to_string(), "Submit flag".to_string()),
+    };
+
+    data.custom_id(custom_id)
+        .title(title)
+        .components(|components| {
+            components.create_action_row(|action| {
+                action.create_input_text(|input| {
+                    input
+                        .custom_id(ID_INPUT_MODAL_FLAG_SUBMIT)
+                        .style(InputTextStyle::Short)
+                        .label("Submit flag here: ")
+                        .required(true)
+                        .min_length(1)
+                        .max_length(100)
+                        .value("")
+                })
+            })
+        })
+}
+
 pub async fn cmd_addchallenge(
     ctx: Context,
     command: ApplicationCommandInteraction,
@@ -110,6 +181,41 @@ pub async fn cmd_addchallenge(
                                             .value("")
                                     })
                                 })
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_POINTS_MODAL_CHAL_ADD)
+                                            .style(InputTextStyle::Short)
+                                            .label("Points:")
+                                            .required(true)
+                                            .min_length(1)
+                                            .max_length(10)
+                                            .value("100")
+                                    })
+                                })
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_CATEGORY_MODAL_CHAL_ADD)
+                                            .style(InputTextStyle::Short)
+                                            .label("Category:")
+                                            .required(true)
+                                            .min_length(1)
+                                            .max_length(50)
+                                            .value("Misc")
+                                    })
+                                })
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_DESCRIPTION_MODAL_CHAL_ADD)
+                                            .style(InputTextStyle::Paragraph)
+                                            .label("Description:")
+                                            .required(false)
+                                            .max_length(2000)
+                                            .value("")
+                                    })
+                                })
                         })
                 })
         })
@@ -152,84 +258,1223 @@ pub async fn modal_chal_add_response(
         .get(0)
         .ok_or(InteractionError::UnprocessableRequest)?;
 
+    let points = get_input_value(&interaction.data.components, 2)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .trim()
+        .parse::<i64>();
+
+    let category = get_input_value(&interaction.data.components, 3)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let description = get_input_value(&interaction.data.components, 4)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
     if let (ActionRowComponent::InputText(name), ActionRowComponent::InputText(flag)) =
         (name_component, flag_component)
     {
-        println!("Add chal: {:?}", (&name.value, &flag.value));
+        println!("Add chal: {:?}", name.value);
+
+        let (title, challenge_id) = match points {
+            Ok(_) if ChallengeStage::flag_in_use(db, &flag.value)? => (
+                "That flag is already in use by another challenge or a decoy!",
+                None,
+            ),
+            Ok(points) => {
+                let id = Challenge::create_challenge(
+                    db,
+                    &name.value,
+                    category,
+                    description,
+                    &flag.value,
+                    points,
+                )?;
+                ("Added challenge successfully!", Some(id))
+            }
+            Err(_) => ("Points must be a whole number!", None),
+        };
 
-        Challenge::create_challenge(db, &name.value, &flag.value)?;
+        let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
 
         interaction
             .create_interaction_response(&ctx.http, |response| {
                 response
                     .kind(InteractionResponseType::ChannelMessageWithSource)
                     .interaction_response_data(|message| {
-                        message.embed(|embed| embed.title("Added challenge successfully!"))
+                        message.flags(flags).embed(|embed| embed.title(title))
                     })
             })
             .await?;
+
+        if let Some(challenge) = challenge_id
+            .map(|id| Challenge::get_by_id(db, id))
+            .transpose()?
+            .flatten()
+        {
+            discussions::create_thread(&ctx, db, &challenge).await?;
+        }
     }
 
     Ok(())
 }
 
-pub async fn modal_submit_flag_response(
+pub async fn cmd_challenge(
     ctx: Context,
     db: &Database,
-    interaction: ModalSubmitInteraction,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
 ) -> InteractionResult {
-    if let ActionRowComponent::InputText(flag) = interaction
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let subcommand = command
         .data
-        .components
+        .options
         .get(0)
-        .ok_or(InteractionError::UnprocessableRequest)?
-        .components
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let challenge_id = get_option(&subcommand.options, "challenge")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let challenge = match Challenge::get_by_id(db, challenge_id)? {
+        Some(challenge) if !challenge.archived => challenge,
+        _ => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(flags)
+                                .embed(|embed| embed.title("No challenge with that id!"))
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    match subcommand.name.as_str() {
+        "edit" => cmd_challenge_edit(ctx, command, challenge).await,
+        "delete" => cmd_challenge_delete(ctx, db, command, challenge).await,
+        "rotate" => cmd_challenge_rotate(ctx, db, command, challenge).await,
+        "thread" => cmd_challenge_thread(ctx, db, command, challenge).await,
+        _ => Err(InteractionError::UnprocessableRequest),
+    }
+}
+
+async fn cmd_challenge_rotate(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    challenge: Challenge,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
         .get(0)
         .ok_or(InteractionError::UnprocessableRequest)?
-    {
-        println!("Flag submitted! {}", flag.value);
+        .options;
 
-        match models::Challenge::get_by_flag(db, &flag.value)? {
-            Some(challenge) => {
-                interaction
-                    .create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|data| {
-                                data.embed(|embed| {
-                                    embed
-                                        .title("Flag correct!")
-                                        .colour((0, 255, 0))
-                                        .description(format!(
-                                            "{} has scored the flag for challenge **{}**",
-                                            interaction.user.mention(),
-                                            challenge.name
-                                        ))
-                                })
+    let stage_id = get_option(options, "stage")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64());
+
+    let grace_minutes = get_option(options, "grace")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .unwrap_or(DEFAULT_FLAG_GRACE_MINUTES)
+        .max(0);
+
+    let stages = ChallengeStage::get_by_challenge(db, challenge.id)?;
+
+    let stage = match stage_id {
+        Some(stage_id) => stages.into_iter().find(|s| s.id == stage_id),
+        // The stage can only be left out if there is no ambiguity
+        None if stages.len() == 1 => stages.into_iter().next(),
+        None => None,
+    };
+
+    let stage = match stage {
+        Some(stage) => stage,
+        None => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(flags).embed(|embed| {
+                                embed.title("Choose which stage of the challenge to rotate!")
                             })
-                    })
-                    .await?
-            }
-            None => {
-                interaction
-                    .create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|data| {
-                                data.embed(|embed| {
-                                    embed
-                                        .title("Flag incorrect!")
-                                        .colour((255, 0, 0))
-                                        .description("Incorrect flag!")
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|message| {
+                    message
+                        .custom_id(format!(
+                            "{}:{}:{}",
+                            ID_MODAL_FLAG_ROTATE, stage.id, grace_minutes
+                        ))
+                        .title(format!("Rotate flag for {}", truncate(&stage.name, 29)))
+                        .components(|components| {
+                            components.create_action_row(|action| {
+                                action.create_input_text(|input| {
+                                    input
+                                        .custom_id(ID_INPUT_FLAG_MODAL_FLAG_ROTATE)
+                                        .style(InputTextStyle::Short)
+                                        .label("New flag:")
+                                        .required(true)
+                                        .min_length(1)
+                                        .max_length(100)
+                                        .value("")
                                 })
                             })
-                    })
-                    .await?
-            }
-        }
+                        })
+                })
+        })
+        .await?;
 
-        Ok(())
+    Ok(())
+}
+
+pub async fn modal_flag_rotate_response(
+    ctx: Context,
+    db: &Database,
+    interaction: ModalSubmitInteraction,
+    argument: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let (stage_id, grace_minutes) = argument
+        .split_once(':')
+        .and_then(|(s, g)| Some((s.parse::<i64>().ok()?, g.parse::<i64>().ok()?)))
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let flag = get_input_value(&interaction.data.components, 0)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    let rotated = if ChallengeStage::flag_in_use(db, flag)? {
+        Err("That flag is already in use by another stage or a decoy!")
     } else {
-        Err(InteractionError::UnprocessableRequest)
+        // The stage may have been deleted since the modal was opened
+        ChallengeStage::rotate_flag(db, stage_id, flag, grace_minutes * 60)?
+            .ok_or("That stage no longer exists!")
+    };
+
+    let old_flag = match rotated {
+        Ok(old_flag) => old_flag,
+        Err(title) => {
+            interaction
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message.flags(flags).embed(|embed| embed.title(title))
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+    AuditLog::record(
+        db,
+        interaction.user.id.0,
+        AuditLog::FLAG_ROTATE,
+        stage_id,
+        Some(&old_flag),
+        Some(flag),
+    )?;
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message.flags(flags).embed(|embed| {
+                        embed
+                            .title("Rotated flag successfully!")
+                            .description(format!(
+                                "The old flag will be accepted for another {} minutes.",
+                                grace_minutes
+                            ))
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn cmd_challenge_edit(
+    ctx: Context,
+    command: ApplicationCommandInteraction,
+    challenge: Challenge,
+) -> InteractionResult {
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|message| {
+                    message
+                        .custom_id(format!("{}:{}", ID_MODAL_CHAL_EDIT, challenge.id))
+                        .title("Edit challenge")
+                        .components(|components| {
+                            components
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_NAME_MODAL_CHAL_EDIT)
+                                            .style(InputTextStyle::Short)
+                                            .label("Challenge name:")
+                                            .required(true)
+                                            .min_length(1)
+                                            .max_length(100)
+                                            .value(&challenge.name)
+                                    })
+                                })
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_CATEGORY_MODAL_CHAL_EDIT)
+                                            .style(InputTextStyle::Short)
+                                            .label("Category:")
+                                            .required(true)
+                                            .min_length(1)
+                                            .max_length(50)
+                                            .value(&challenge.category)
+                                    })
+                                })
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_DESCRIPTION_MODAL_CHAL_EDIT)
+                                            .style(InputTextStyle::Paragraph)
+                                            .label("Description:")
+                                            .required(false)
+                                            .max_length(2000)
+                                            .value(&challenge.description)
+                                    })
+                                })
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn modal_chal_edit_response(
+    ctx: Context,
+    db: &Database,
+    interaction: ModalSubmitInteraction,
+    challenge_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let challenge_id = challenge_id
+        .parse::<i64>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    let challenge =
+        Challenge::get_by_id(db, challenge_id)?.ok_or(InteractionError::UnprocessableRequest)?;
+
+    let name = get_input_value(&interaction.data.components, 0)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let category = get_input_value(&interaction.data.components, 1)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let description = get_input_value(&interaction.data.components, 2)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    Challenge::set_details(db, challenge.id, name, category, description)?;
+
+    // Keep the previous value of everything that changed
+    for (action, old_value, new_value) in [
+        (AuditLog::CHALLENGE_RENAME, &challenge.name, name),
+        (
+            AuditLog::CHALLENGE_RECATEGORISE,
+            &challenge.category,
+            category,
+        ),
+        (
+            AuditLog::CHALLENGE_REDESCRIBE,
+            &challenge.description,
+            description,
+        ),
+    ] {
+        if old_value != new_value {
+            AuditLog::record(
+                db,
+                interaction.user.id.0,
+                action,
+                challenge.id,
+                Some(old_value),
+                Some(new_value),
+            )?;
+        }
+    }
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message
+                        .flags(flags)
+                        .embed(|embed| embed.title("Edited challenge successfully!"))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn cmd_challenge_delete(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    challenge: Challenge,
+) -> InteractionResult {
+    Challenge::archive(db, challenge.id)?;
+    AuditLog::record(
+        db,
+        command.user.id.0,
+        AuditLog::CHALLENGE_ARCHIVE,
+        challenge.id,
+        None,
+        None,
+    )?;
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Deleted challenge successfully!")
+                            .description(format!(
+                                "**{}** has been archived, its flags are no longer accepted. \
+                                Existing solves are kept.",
+                                challenge.name
+                            ))
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Creates the discussion thread of a challenge that doesn't have one, e.g. because it was added
+/// before the discussion channel was set.
+async fn cmd_challenge_thread(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    challenge: Challenge,
+) -> InteractionResult {
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    // Adding every existing solver to the thread takes a while
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags))
+        })
+        .await?;
+
+    let description = match challenge.thread_id {
+        Some(thread_id) => format!(
+            "**{}** already has a discussion thread: {}",
+            challenge.name,
+            ChannelId(thread_id).mention()
+        ),
+        None => match discussions::create_thread(&ctx, db, &challenge).await? {
+            Some(thread_id) => format!(
+                "Created {} for **{}**.",
+                thread_id.mention(),
+                challenge.name
+            ),
+            None => "Set a discussion channel with `/config discussionchannel` first!".to_string(),
+        },
+    };
+
+    command
+        .create_followup_message(&ctx.http, |message| {
+            message.embed(|embed| embed.title("Discussion thread").description(description))
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn cmd_addstage(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let challenge_id = get_option(&command.data.options, "challenge")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let challenge = match Challenge::get_by_id(db, challenge_id)? {
+        Some(challenge) if !challenge.archived => challenge,
+        _ => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(flags)
+                                .embed(|embed| embed.title("No challenge with that id!"))
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let stage_count = ChallengeStage::get_by_challenge(db, challenge.id)?.len();
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|message| {
+                    message
+                        .custom_id(format!("{}:{}", ID_MODAL_STAGE_ADD, challenge.id))
+                        .title(format!("Add a stage to {}", truncate(&challenge.name, 30)))
+                        .components(|components| {
+                            components
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_NAME_MODAL_STAGE_ADD)
+                                            .style(InputTextStyle::Short)
+                                            .label("Stage name:")
+                                            .required(true)
+                                            .min_length(1)
+                                            .max_length(100)
+                                            .value(format!("Stage {}", stage_count + 1))
+                                    })
+                                })
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_FLAG_MODAL_STAGE_ADD)
+                                            .style(InputTextStyle::Short)
+                                            .label("Flag:")
+                                            .required(true)
+                                            .min_length(1)
+                                            .max_length(100)
+                                            .value("")
+                                    })
+                                })
+                                .create_action_row(|action| {
+                                    action.create_input_text(|input| {
+                                        input
+                                            .custom_id(ID_INPUT_POINTS_MODAL_STAGE_ADD)
+                                            .style(InputTextStyle::Short)
+                                            .label("Points:")
+                                            .required(true)
+                                            .min_length(1)
+                                            .max_length(10)
+                                            .value("100")
+                                    })
+                                })
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn modal_stage_add_response(
+    ctx: Context,
+    db: &Database,
+    interaction: ModalSubmitInteraction,
+    challenge_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let challenge_id = challenge_id
+        .parse::<i64>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    let name = get_input_value(&interaction.data.components, 0)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let flag = get_input_value(&interaction.data.components, 1)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let points = get_input_value(&interaction.data.components, 2)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .trim()
+        .parse::<i64>();
+
+    println!("Add stage: {:?}", (challenge_id, name));
+
+    let (title, description) = match points {
+        Ok(_) if ChallengeStage::flag_in_use(db, flag)? => (
+            "That flag is already in use by another stage or a decoy!",
+            None,
+        ),
+        Ok(points) => {
+            // Solving a challenge means having found the flags of all of its stages, so everyone
+            // who had solved it stops counting as a solver until they find the new flag too
+            let solvers = Solve::get_solver_ids(db, challenge_id)?.len();
+            ChallengeStage::create(db, challenge_id, name, flag, points)?;
+            (
+                "Added stage successfully!",
+                if solvers == 0 {
+                    None
+                } else {
+                    Some(format!(
+                        "The {} user{} who had solved this challenge will need to find this \
+                        stage's flag to solve it again.",
+                        solvers,
+                        if solvers == 1 { "" } else { "s" }
+                    ))
+                },
+            )
+        }
+        Err(_) => ("Points must be a whole number!", None),
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message.flags(flags).embed(|embed| {
+                        if let Some(description) = description {
+                            embed.description(description);
+                        }
+                        embed.title(title)
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn cmd_wronganswer(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let subcommand = command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let title = match subcommand.name.as_str() {
+        "add" => {
+            let challenge_id = get_option(&subcommand.options, "challenge")
+                .and_then(|o| o.value.as_ref())
+                .and_then(|v| v.as_i64())
+                .ok_or(InteractionError::UnprocessableRequest)?;
+
+            let answer = get_option(&subcommand.options, "answer")
+                .and_then(|o| o.value.as_ref())
+                .and_then(|v| v.as_str())
+                .ok_or(InteractionError::UnprocessableRequest)?;
+
+            let message = get_option(&subcommand.options, "message")
+                .and_then(|o| o.value.as_ref())
+                .and_then(|v| v.as_str())
+                .ok_or(InteractionError::UnprocessableRequest)?;
+
+            if matches!(Challenge::get_by_id(db, challenge_id)?, Some(c) if !c.archived) {
+                KnownWrongAnswer::create(db, challenge_id, answer, message)?;
+                "Added known wrong answer successfully!".to_string()
+            } else {
+                "No challenge with that id!".to_string()
+            }
+        }
+        "list" => {
+            let challenge_id = get_option(&subcommand.options, "challenge")
+                .and_then(|o| o.value.as_ref())
+                .and_then(|v| v.as_i64())
+                .ok_or(InteractionError::UnprocessableRequest)?;
+
+            let answers = KnownWrongAnswer::get_by_challenge(db, challenge_id)?;
+
+            let description = if answers.is_empty() {
+                "No known wrong answers for this challenge.".to_string()
+            } else {
+                answers
+                    .iter()
+                    .map(|a| format!("`{}` ||`{}`||: {}", a.id, a.answer, a.message))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                                .embed(|embed| {
+                                    embed
+                                        .title("Known wrong answers")
+                                        .description(truncate(&description, 4096))
+                                })
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+        "remove" => {
+            let id = get_option(&subcommand.options, "id")
+                .and_then(|o| o.value.as_ref())
+                .and_then(|v| v.as_i64())
+                .ok_or(InteractionError::UnprocessableRequest)?;
+
+            if KnownWrongAnswer::delete(db, id)? {
+                "Removed known wrong answer successfully!".to_string()
+            } else {
+                "No known wrong answer with that id!".to_string()
+            }
+        }
+        _ => return Err(InteractionError::UnprocessableRequest),
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| embed.title(title))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Suggests challenges matching what the user has typed so far into a `challenge` option, or
+/// stages of the chosen challenge for a `stage` option.
+pub async fn autocomplete_challenge(
+    ctx: Context,
+    db: &Database,
+    autocomplete: AutocompleteInteraction,
+) -> InteractionResult {
+    // The options may be nested inside a subcommand
+    let options = match autocomplete.data.options.get(0) {
+        Some(subcommand) if subcommand.kind == ApplicationCommandOptionType::SubCommand => {
+            &subcommand.options
+        }
+        _ => &autocomplete.data.options,
+    };
+
+    let focused = options
+        .iter()
+        .find(|o| o.focused)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let typed = focused
+        .value
+        .as_ref()
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    // Discord allows at most 25 choices
+    let choices: Vec<(String, i64)> = if focused.name == "stage" {
+        let challenge_id = get_option(options, "challenge")
+            .and_then(|o| o.value.as_ref())
+            .and_then(|v| v.as_i64().or_else(|| v.as_str()?.parse().ok()));
+
+        match challenge_id {
+            Some(challenge_id) => ChallengeStage::get_by_challenge(db, challenge_id)?
+                .into_iter()
+                .filter(|s| s.name.to_lowercase().contains(&typed))
+                .take(25)
+                .map(|s| (s.name, s.id))
+                .collect(),
+            None => Vec::new(),
+        }
+    } else {
+        Challenge::get_all(db)?
+            .into_iter()
+            .filter(|c| c.name.to_lowercase().contains(&typed))
+            .take(25)
+            .map(|c| (c.name, c.id))
+            .collect()
+    };
+
+    autocomplete
+        .create_autocomplete_response(&ctx.http, |response| {
+            for (name, id) in choices {
+                response.add_int_choice(name, id);
+            }
+            response
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Checks a submitted flag, against only the stages of `challenge_id` if the modal was opened for a
+/// specific challenge.
+pub async fn modal_submit_flag_response(
+    ctx: Context,
+    db: &Database,
+    interaction: ModalSubmitInteraction,
+    challenge_id: Option<&str>,
+) -> InteractionResult {
+    let challenge_id = challenge_id
+        .map(|id| id.parse::<i64>())
+        .transpose()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    if let ActionRowComponent::InputText(flag) = interaction
+        .data
+        .components
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .components
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+    {
+        println!("Flag submitted by {}", interaction.user.id);
+
+        let sanction = Sanction::get_by_user(db, interaction.user.id.0)?;
+
+        if matches!(&sanction, Some(s) if s.kind == Sanction::DISQUALIFY) {
+            Submission::create(db, interaction.user.id.0, &flag.value, None)?;
+
+            interaction
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                                .embed(|embed| {
+                                    embed
+                                        .title("Disqualified!")
+                                        .colour((255, 0, 0))
+                                        .description(
+                                            "You have been disqualified from the competition, \
+                                            so your submissions are no longer accepted.",
+                                        )
+                                })
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+
+        let shadow_banned = matches!(&sanction, Some(s) if s.kind == Sanction::SHADOW_BAN);
+
+        // Flags that have been rotated are still accepted during their grace period
+        let stage = match ChallengeStage::get_by_flag(db, &flag.value)? {
+            Some(stage) => Some((stage, false)),
+            None => ChallengeStage::get_by_grace_flag(db, &flag.value)?.map(|s| (s, true)),
+        };
+
+        match stage {
+            // When submitting for a specific challenge, flags for other challenges don't count
+            Some((stage, _)) if matches!(challenge_id, Some(id) if id != stage.challenge_id) => {
+                Submission::create(db, interaction.user.id.0, &flag.value, None)?;
+
+                let flags = response_flags(db, Setting::OUTCOME_INCORRECT)?;
+
+                interaction
+                    .create_interaction_response(&ctx.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|data| {
+                                data.flags(flags).embed(|embed| {
+                                    embed
+                                        .title("Flag incorrect!")
+                                        .colour((255, 0, 0))
+                                        .description(
+                                            "That is a valid flag, but not for this challenge!",
+                                        )
+                                })
+                            })
+                    })
+                    .await?
+            }
+            Some((stage, grace)) => {
+                Submission::create(
+                    db,
+                    interaction.user.id.0,
+                    &flag.value,
+                    Some(stage.challenge_id),
+                )?;
+
+                respond_flag_correct(&ctx, db, &interaction, &stage, grace, shadow_banned).await?
+            }
+            None => {
+                let decoy = DecoyFlag::get_by_flag(db, &flag.value)?;
+
+                if let Some(decoy) = &decoy {
+                    decoys::record_decoy_submission(db, &interaction.user, decoy)?;
+                }
+
+                respond_flag_wrong(
+                    &ctx,
+                    db,
+                    &interaction,
+                    &flag.value,
+                    challenge_id,
+                    decoy.is_some(),
+                )
+                .await?;
+
+                if let Some(decoy) = &decoy {
+                    decoys::notify_decoy_submission(&ctx, db, &interaction.user, decoy).await?;
+
+                    // The penalty may have taken the user below a score reward
+                    rewards::sync_roles(&ctx, db, interaction.guild_id, interaction.user.id)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
+    } else {
+        Err(InteractionError::UnprocessableRequest)
+    }
+}
+
+/// Records that the submitter found the flag for `stage` and responds with their progress through
+/// the challenge. Solving the whole challenge is announced in the solves channel, if there is one,
+/// in which case the response is always only shown to the submitter.
+///
+/// Shadow banned submitters get the same response, but nothing is recorded or announced.
+async fn respond_flag_correct(
+    ctx: &Context,
+    db: &Database,
+    interaction: &ModalSubmitInteraction,
+    stage: &ChallengeStage,
+    grace: bool,
+    shadow_banned: bool,
+) -> InteractionResult {
+    let challenge = Challenge::get_by_id(db, stage.challenge_id)?
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let mut solved_stage_ids =
+        StageSolve::get_solved_stage_ids(db, interaction.user.id.0, challenge.id)?;
+
+    let newly_solved = if shadow_banned {
+        !solved_stage_ids.contains(&stage.id)
+    } else {
+        StageSolve::create(db, interaction.user.id.0, stage.id, grace)?
+    };
+
+    if newly_solved {
+        solved_stage_ids.push(stage.id);
+    }
+
+    let stages = ChallengeStage::get_by_challenge(db, challenge.id)?;
+
+    let solved_challenge = newly_solved && solved_stage_ids.len() == stages.len() && !shadow_banned;
+
+    let blood = if solved_challenge {
+        let place = *Solve::get_counts(db)?.get(&challenge.id).unwrap_or(&0);
+        let bonus =
+            announcements::award_blood_bonus(ctx, db, interaction.user.id.0, &challenge, place)
+                .await?;
+
+        Some((place, bonus))
+    } else {
+        None
+    };
+
+    // Solves announced in the solves channel are only confirmed to the submitter
+    let flags = if Setting::get_id(db, Setting::SOLVES_CHANNEL)?.is_some() {
+        InteractionApplicationCommandCallbackDataFlags::EPHEMERAL
+    } else {
+        response_flags(db, Setting::OUTCOME_CORRECT)?
+    };
+
+    let mut description = if !newly_solved {
+        format!(
+            "{} has already scored this flag for challenge **{}**",
+            interaction.user.mention(),
+            challenge.name
+        )
+    } else if stages.len() == 1 {
+        format!(
+            "{} has scored the flag for challenge **{}** (+{} points)",
+            interaction.user.mention(),
+            challenge.name,
+            stage.points
+        )
+    } else {
+        let progress = format!(
+            "{}/{} stages complete",
+            solved_stage_ids.len(),
+            stages.len()
+        );
+
+        if solved_stage_ids.len() == stages.len() {
+            format!(
+                "{} has scored the flag for stage **{}** of challenge **{}** (+{} points), \
+                solving the challenge! ({})",
+                interaction.user.mention(),
+                stage.name,
+                challenge.name,
+                stage.points,
+                progress
+            )
+        } else {
+            format!(
+                "{} has scored the flag for stage **{}** of challenge **{}** (+{} points) ({})",
+                interaction.user.mention(),
+                stage.name,
+                challenge.name,
+                stage.points,
+                progress
+            )
+        }
+    };
+
+    if let Some((place, bonus)) = blood {
+        if let Some(title) = announcements::blood_title(place) {
+            description.push_str(&format!("\n\n**{}**", title));
+
+            if bonus > 0 {
+                description.push_str(&format!(" (+{} bonus points)", bonus));
+            }
+        }
+    }
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Flag correct!")
+                            .colour((0, 255, 0))
+                            .description(description);
+
+                        if grace && newly_solved {
+                            embed.footer(|footer| {
+                                footer.text(
+                                    "This flag has been rotated and was accepted during its \
+                                    grace period.",
+                                )
+                            });
+                        }
+
+                        embed
+                    })
+                })
+        })
+        .await?;
+
+    if let Some((place, bonus)) = blood {
+        announcements::announce_solve(ctx, db, &interaction.user, &challenge, place, bonus).await?;
+    }
+
+    if solved_challenge {
+        discussions::add_solver(ctx, &challenge, interaction.user.id).await?;
+    }
+
+    if newly_solved && !shadow_banned {
+        rewards::sync_roles(ctx, db, interaction.guild_id, interaction.user.id).await?;
+    }
+
+    Ok(())
+}
+
+/// Responds to a submission of a flag that isn't correct for any challenge.
+///
+/// Known wrong answers get their custom message, whatever they look like. Otherwise malformed flags
+/// are rejected without being counted as an attempt, unless they are `decoy` flags, and the
+/// response is the generic "Flag incorrect!".
+async fn respond_flag_wrong(
+    ctx: &Context,
+    db: &Database,
+    interaction: &ModalSubmitInteraction,
+    flag: &str,
+    challenge_id: Option<i64>,
+    decoy: bool,
+) -> InteractionResult {
+    let flags = response_flags(db, Setting::OUTCOME_INCORRECT)?;
+
+    let known = KnownWrongAnswer::get_by_answer(db, flag, challenge_id)?;
+
+    // Decoy submissions have already been recorded and penalised, so they always count, and known
+    // wrong answers are often intermediate steps that don't look like flags
+    let format = if decoy || known.is_some() {
+        None
+    } else {
+        Setting::get(db, Setting::FLAG_FORMAT)?
+    };
+
+    if let Some(prefix) = format {
+        if let Some(problem) = check_flag_format(&prefix, flag) {
+            interaction
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(flags).embed(|embed| {
+                                embed
+                                    .title("Flag malformed!")
+                                    .colour((255, 165, 0))
+                                    .description(problem)
+                                    .footer(|footer| {
+                                        footer
+                                            .text("This submission was not counted as an attempt.")
+                                    })
+                            })
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    }
+
+    Submission::create(db, interaction.user.id.0, flag, None)?;
+
+    let description = match known {
+        Some(answer) => answer.message,
+        None => "Incorrect flag!".to_string(),
+    };
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Flag incorrect!")
+                            .colour((255, 0, 0))
+                            .description(description)
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Checks `flag` against the flag format `<prefix>{...}`, returning an explanation for the player
+/// if it is malformed.
+fn check_flag_format(prefix: &str, flag: &str) -> Option<String> {
+    let format = format!("`{}{{...}}`", prefix);
+
+    match flag
+        .strip_prefix(prefix)
+        .and_then(|f| f.strip_prefix('{'))
+        .and_then(|f| f.strip_suffix('}'))
+    {
+        Some("") | Some("...") => Some(format!(
+            "That's the example flag format! Flags look like {}, with the `...` replaced by the \
+            real flag.",
+            format
+        )),
+        Some(_) => None,
+        None => match flag.split_once('{') {
+            Some((other_prefix, _)) if flag.ends_with('}') => Some(format!(
+                "Flags look like {}, not `{}{{...}}`.",
+                format, other_prefix
+            )),
+            _ => Some(format!("Flags look like {}.", format)),
+        },
     }
 }
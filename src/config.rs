@@ -0,0 +1,195 @@
+use serenity::{
+    client::Context,
+    model::{
+        id::{ChannelId, RoleId},
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
+            },
+            InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{models::Setting, Database},
+    get_option, response_flags, InteractionError, InteractionResult,
+};
+
+pub async fn cmd_config(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let subcommand = command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let description = match subcommand.name.as_str() {
+        "adminchannel" => set_channel_setting(
+            db,
+            &subcommand.options,
+            Setting::ADMIN_CHANNEL,
+            "Admin channel set to",
+            "Admin channel cleared",
+        )?,
+        // Omitting the channel disables announcements
+        "solveschannel" => set_channel_setting(
+            db,
+            &subcommand.options,
+            Setting::SOLVES_CHANNEL,
+            "Solves will be announced in",
+            "Solve announcements disabled",
+        )?,
+        // Omitting the channel stops threads being created for new challenges
+        "discussionchannel" => set_channel_setting(
+            db,
+            &subcommand.options,
+            Setting::DISCUSSION_CHANNEL,
+            "Challenge discussion threads will be created in",
+            "Challenge discussion threads disabled",
+        )?,
+        // Omitting the channel puts tickets wherever they are opened
+        "ticketchannel" => set_channel_setting(
+            db,
+            &subcommand.options,
+            Setting::TICKET_CHANNEL,
+            "Ticket threads will be created in",
+            "Ticket threads will be created where /ticket is run",
+        )?,
+        "bloodbonus" => {
+            // Omitted places get no bonus
+            let bonuses = ["first", "second", "third"]
+                .iter()
+                .map(|name| {
+                    get_option(&subcommand.options, name)
+                        .and_then(|o| o.value.as_ref())
+                        .and_then(|v| v.as_i64())
+                        .unwrap_or(0)
+                })
+                .collect::<Vec<_>>();
+
+            if bonuses.iter().all(|&bonus| bonus == 0) {
+                Setting::unset(db, Setting::BLOOD_BONUS)?;
+                "Blood bonuses disabled".to_string()
+            } else {
+                Setting::set(
+                    db,
+                    Setting::BLOOD_BONUS,
+                    &bonuses
+                        .iter()
+                        .map(|bonus| bonus.to_string())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                )?;
+                format!(
+                    "The first three solves of a challenge now get +{}, +{} and +{} bonus points",
+                    bonuses[0], bonuses[1], bonuses[2]
+                )
+            }
+        }
+        "publicresponses" => {
+            // Omitted outcomes stay private
+            let outcomes = [
+                Setting::OUTCOME_CORRECT,
+                Setting::OUTCOME_INCORRECT,
+                Setting::OUTCOME_ADMIN,
+            ]
+            .iter()
+            .filter(|outcome| {
+                get_option(&subcommand.options, outcome)
+                    .and_then(|o| o.value.as_ref())
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+            if outcomes.is_empty() {
+                Setting::unset(db, Setting::PUBLIC_OUTCOMES)?;
+                "All responses are now only shown to the user".to_string()
+            } else {
+                Setting::set(db, Setting::PUBLIC_OUTCOMES, &outcomes.join(","))?;
+                format!("Responses are now public for: {}", outcomes.join(", "))
+            }
+        }
+        "flagformat" => {
+            // Omitting the prefix disables flag format validation
+            let prefix = get_option(&subcommand.options, "prefix")
+                .and_then(|o| o.value.as_ref())
+                .and_then(|v| v.as_str());
+
+            match prefix {
+                Some(prefix) => {
+                    Setting::set(db, Setting::FLAG_FORMAT, prefix)?;
+                    format!("Flags must now look like `{}{{...}}`", prefix)
+                }
+                None => {
+                    Setting::unset(db, Setting::FLAG_FORMAT)?;
+                    "Flag format validation disabled".to_string()
+                }
+            }
+        }
+        _ => return Err(InteractionError::UnprocessableRequest),
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Configuration updated")
+                            .description(description)
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Sets the channel setting `key` to the `channel` option, or clears it if that is omitted,
+/// describing the change as `set` followed by the channel, or as `unset`.
+fn set_channel_setting(
+    db: &Database,
+    options: &[ApplicationCommandInteractionDataOption],
+    key: &str,
+    set: &str,
+    unset: &str,
+) -> Result<String, InteractionError> {
+    let channel_id = get_option(options, "channel")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(|v| v.parse::<u64>())
+        .transpose()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    match channel_id {
+        Some(channel_id) => {
+            Setting::set(db, key, &channel_id.to_string())?;
+            Ok(format!("{} {}", set, ChannelId(channel_id).mention()))
+        }
+        None => {
+            Setting::unset(db, key)?;
+            Ok(unset.to_string())
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Mutex;
 
-use sqlite::Connection;
+use sqlite::{Connection, State};
 
 pub mod models;
 
@@ -37,12 +37,255 @@ impl Database {
             .execute(
                 "
                 CREATE TABLE IF NOT EXISTS challenges (id INTEGER PRIMARY KEY, name TEXT, flag TEXT);
+                CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT);
+                CREATE TABLE IF NOT EXISTS decoy_flags (
+                    id INTEGER PRIMARY KEY,
+                    flag TEXT UNIQUE,
+                    description TEXT,
+                    penalty INTEGER NOT NULL DEFAULT 0
+                );
+                CREATE TABLE IF NOT EXISTS incidents (
+                    id INTEGER PRIMARY KEY,
+                    user_id INTEGER NOT NULL,
+                    decoy_id INTEGER NOT NULL,
+                    flag TEXT NOT NULL,
+                    penalty INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                CREATE TABLE IF NOT EXISTS submissions (
+                    id INTEGER PRIMARY KEY,
+                    user_id INTEGER NOT NULL,
+                    flag TEXT NOT NULL,
+                    challenge_id INTEGER,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                CREATE TABLE IF NOT EXISTS known_wrong_answers (
+                    id INTEGER PRIMARY KEY,
+                    challenge_id INTEGER NOT NULL,
+                    answer TEXT NOT NULL,
+                    message TEXT NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS challenge_stages (
+                    id INTEGER PRIMARY KEY,
+                    challenge_id INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    flag TEXT NOT NULL UNIQUE,
+                    points INTEGER NOT NULL DEFAULT 0
+                );
+                CREATE TABLE IF NOT EXISTS stage_solves (
+                    user_id INTEGER NOT NULL,
+                    stage_id INTEGER NOT NULL,
+                    solved_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    PRIMARY KEY (user_id, stage_id)
+                );
+                CREATE TABLE IF NOT EXISTS retired_flags (
+                    id INTEGER PRIMARY KEY,
+                    stage_id INTEGER NOT NULL,
+                    flag TEXT NOT NULL,
+                    retired_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    grace_until INTEGER NOT NULL
+                );
+                CREATE VIEW IF NOT EXISTS solves AS
+                    SELECT stage_solves.user_id, challenge_stages.challenge_id,
+                        MAX(stage_solves.solved_at) AS solved_at
+                    FROM stage_solves
+                    JOIN challenge_stages ON challenge_stages.id = stage_solves.stage_id
+                    GROUP BY stage_solves.user_id, challenge_stages.challenge_id
+                    HAVING COUNT(*) = (
+                        SELECT COUNT(*) FROM challenge_stages AS stages
+                        WHERE stages.challenge_id = challenge_stages.challenge_id
+                    );
+                CREATE TABLE IF NOT EXISTS audit_log (
+                    id INTEGER PRIMARY KEY,
+                    actor_id INTEGER NOT NULL,
+                    action TEXT NOT NULL,
+                    target_id INTEGER NOT NULL,
+                    old_value TEXT,
+                    new_value TEXT,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                CREATE TABLE IF NOT EXISTS sanctions (
+                    user_id INTEGER PRIMARY KEY,
+                    kind TEXT NOT NULL,
+                    reason TEXT NOT NULL,
+                    actor_id INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                CREATE TABLE IF NOT EXISTS role_rewards (
+                    id INTEGER PRIMARY KEY,
+                    role_id INTEGER NOT NULL,
+                    challenge_id INTEGER,
+                    category TEXT,
+                    score INTEGER
+                );
+                CREATE TABLE IF NOT EXISTS tickets (
+                    id INTEGER PRIMARY KEY,
+                    user_id INTEGER NOT NULL,
+                    challenge_id INTEGER,
+                    description TEXT NOT NULL,
+                    thread_id INTEGER,
+                    status TEXT NOT NULL DEFAULT 'open',
+                    claimed_by INTEGER,
+                    transcript TEXT,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    closed_at INTEGER
+                );
+                CREATE TABLE IF NOT EXISTS scheduled_messages (
+                    id INTEGER PRIMARY KEY,
+                    channel_id INTEGER NOT NULL,
+                    content TEXT NOT NULL,
+                    send_at INTEGER NOT NULL,
+                    cron TEXT,
+                    created_by INTEGER NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS bot_messages (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT UNIQUE,
+                    channel_id INTEGER NOT NULL,
+                    message_id INTEGER NOT NULL UNIQUE,
+                    created_by INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                CREATE TABLE IF NOT EXISTS bot_message_revisions (
+                    id INTEGER PRIMARY KEY,
+                    bot_message_id INTEGER NOT NULL,
+                    json TEXT NOT NULL,
+                    actor_id INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                CREATE TABLE IF NOT EXISTS self_roles (
+                    role_id INTEGER PRIMARY KEY,
+                    role_group TEXT NOT NULL,
+                    label TEXT NOT NULL,
+                    actor_id INTEGER NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS exclusive_role_groups (role_group TEXT PRIMARY KEY);
+                CREATE TABLE IF NOT EXISTS crosspost_channels (channel_id INTEGER PRIMARY KEY);
+                CREATE TABLE IF NOT EXISTS pending_drafts (
+                    id INTEGER PRIMARY KEY,
+                    kind TEXT NOT NULL,
+                    user_id INTEGER NOT NULL,
+                    channel_id INTEGER NOT NULL,
+                    message_id INTEGER,
+                    name TEXT,
+                    json TEXT NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                CREATE TABLE IF NOT EXISTS score_adjustments (
+                    id INTEGER PRIMARY KEY,
+                    user_id INTEGER NOT NULL,
+                    points INTEGER NOT NULL,
+                    reason TEXT NOT NULL,
+                    actor_id INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
                 ",
             )
             .unwrap();
 
+        // Challenges used to have a single flag stored on the challenge itself, move any such flags
+        // into a stage of their own. Those flags weren't unique, so only the first challenge with
+        // each flag gets a stage. The others keep their flag here, and are reported until they are
+        // given a stage with `/addstage`. Challenges had no points back then, so neither do stages
+        connection
+            .execute(
+                "
+                INSERT OR IGNORE INTO challenge_stages (challenge_id, name, flag)
+                    SELECT id, 'Flag', flag FROM challenges
+                    WHERE flag IS NOT NULL
+                        AND id NOT IN (SELECT challenge_id FROM challenge_stages)
+                    ORDER BY id;
+                UPDATE challenges SET flag = NULL
+                    WHERE id IN (SELECT challenge_id FROM challenge_stages);
+                ",
+            )
+            .unwrap();
+
+        let mut statement = connection
+            .prepare("SELECT id, name FROM challenges WHERE flag IS NOT NULL")
+            .unwrap();
+        while let State::Row = statement.next().unwrap() {
+            println!(
+                "Challenge #{} ({}) shares its old flag with another challenge, so can't be solved \
+                until it is given a stage with /addstage",
+                statement.read::<i64>(0).unwrap(),
+                statement
+                    .read::<Option<String>>(1)
+                    .unwrap()
+                    .unwrap_or_default()
+            );
+        }
+
+        add_column(&connection, "challenges", "archived_at", "INTEGER").unwrap();
+        add_column(&connection, "challenges", "thread_id", "INTEGER").unwrap();
+        add_column(
+            &connection,
+            "challenges",
+            "category",
+            "TEXT NOT NULL DEFAULT 'Misc'",
+        )
+        .unwrap();
+        add_column(
+            &connection,
+            "challenges",
+            "description",
+            "TEXT NOT NULL DEFAULT ''",
+        )
+        .unwrap();
+        add_column(
+            &connection,
+            "stage_solves",
+            "grace",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .unwrap();
+        add_column(&connection, "bot_messages", "live", "TEXT").unwrap();
+        add_column(&connection, "bot_messages", "live_argument", "TEXT").unwrap();
+        add_column(&connection, "bot_messages", "live_json", "TEXT").unwrap();
+        add_column(&connection, "bot_messages", "role_group", "TEXT").unwrap();
+        add_column(&connection, "bot_messages", "role_style", "TEXT").unwrap();
+        add_column(
+            &connection,
+            "pending_drafts",
+            "mentions",
+            "TEXT NOT NULL DEFAULT 'none'",
+        )
+        .unwrap();
+        add_column(&connection, "pending_drafts", "send_at", "INTEGER").unwrap();
+        add_column(&connection, "pending_drafts", "cron", "TEXT").unwrap();
+        add_column(&connection, "pending_drafts", "scheduled_id", "INTEGER").unwrap();
+        add_column(
+            &connection,
+            "scheduled_messages",
+            "mentions",
+            "TEXT NOT NULL DEFAULT 'none'",
+        )
+        .unwrap();
+        add_column(&connection, "scheduled_messages", "edited_by", "INTEGER").unwrap();
+
         Database {
             connection: Mutex::new(connection),
         }
     }
 }
+
+/// Adds a column to an existing table, unless it already has it.
+///
+/// `CREATE TABLE IF NOT EXISTS` leaves tables from older databases untouched, so columns added
+/// after a table was first created must be added with this.
+fn add_column(connection: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut statement = connection.prepare(format!("PRAGMA table_info({});", table))?;
+
+    while let State::Row = statement.next()? {
+        if statement.read::<String>(1)? == column {
+            return Ok(());
+        }
+    }
+
+    connection.execute(format!(
+        "ALTER TABLE {} ADD COLUMN {} {};",
+        table, column, definition
+    ))?;
+
+    Ok(())
+}
@@ -1,71 +1,2127 @@
-use sqlite::State;
+use std::collections::HashMap;
+
+use sqlite::{Connection, State, Statement};
 
 use super::{Database, Result};
 
 pub struct Challenge {
     pub id: i64,
     pub name: String,
-    pub flag: String,
+    pub category: String,
+    pub description: String,
+    /// Total points across all stages of the challenge.
+    pub points: i64,
+    pub archived: bool,
+    /// Private thread that solvers of the challenge are added to.
+    pub thread_id: Option<u64>,
 }
 
 impl Challenge {
-    /// Creates a new challenge row in the database, returning the id.
-    pub fn create_challenge(db: &Database, name: &str, flag: &str) -> Result<i64> {
-        let connection = db.connection.lock()?;
+    /// Columns read by [`Challenge::from_row`].
+    const COLUMNS: &'static str = "id, name, category, description,
+        (SELECT COALESCE(SUM(points), 0) FROM challenge_stages WHERE challenge_id = challenges.id),
+        archived_at IS NOT NULL, thread_id";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            name: statement.read(1).unwrap(),
+            category: statement.read(2).unwrap(),
+            description: statement.read(3).unwrap(),
+            points: statement.read(4).unwrap(),
+            archived: statement.read::<i64>(5).unwrap() != 0,
+            thread_id: statement
+                .read::<Option<i64>>(6)
+                .unwrap()
+                .map(|id| id as u64),
+        }
+    }
 
-        let mut stmt1 = connection.prepare("INSERT INTO challenges (name, flag) VALUES (?, ?);")?;
-        stmt1.bind(1, name)?;
-        stmt1.bind(2, flag)?;
+    /// Creates a new challenge row in the database along with its first stage, returning the id.
+    pub fn create_challenge(
+        db: &Database,
+        name: &str,
+        category: &str,
+        description: &str,
+        flag: &str,
+        points: i64,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
 
-        assert_eq!(stmt1.next()?, State::Done);
+        // A challenge without its stage could never be solved, so neither is kept if the stage's
+        // flag is already taken
+        transaction(&connection, || {
+            let mut stmt1 = connection.prepare(
+                "INSERT INTO challenges (name, category, description) VALUES (?, ?, ?);",
+            )?;
+            stmt1.bind(1, name)?;
+            stmt1.bind(2, category)?;
+            stmt1.bind(3, description)?;
 
-        let mut stmt2 = connection.prepare("SELECT last_insert_rowid();")?;
+            assert_eq!(stmt1.next()?, State::Done);
 
-        assert_eq!(stmt2.next()?, State::Row);
+            let id = last_insert_rowid(&connection)?;
 
-        let id: i64 = stmt2.read(0).unwrap();
+            let mut stmt2 = connection.prepare(
+                "INSERT INTO challenge_stages (challenge_id, name, flag, points)
+                VALUES (?, ?, ?, ?);",
+            )?;
+            stmt2.bind(1, id)?;
+            stmt2.bind(2, "Flag")?;
+            stmt2.bind(3, flag)?;
+            stmt2.bind(4, points)?;
 
-        assert_eq!(stmt2.next()?, State::Done);
+            assert_eq!(stmt2.next()?, State::Done);
 
-        Ok(id)
+            Ok(id)
+        })
     }
 
     pub fn get_by_id(db: &Database, id: i64) -> Result<Option<Self>> {
         let connection = db.connection.lock()?;
 
-        let mut statement =
-            connection.prepare("SELECT id, name, flag FROM challenges WHERE id = ?")?;
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM challenges WHERE id = ?",
+            Self::COLUMNS
+        ))?;
 
         statement.bind(1, id).unwrap();
 
         if let State::Row = statement.next()? {
-            Ok(Some(Self {
-                id: statement.read(0).unwrap(),
-                name: statement.read(1).unwrap(),
-                flag: statement.read(2).unwrap(),
-            }))
+            Ok(Some(Self::from_row(&statement)))
         } else {
             Ok(None)
         }
     }
 
-    /// Fetches a single challenge row from the database keyed by flag.
+    /// Fetches all challenges that haven't been archived.
+    pub fn get_all(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM challenges WHERE archived_at IS NULL ORDER BY category, id",
+            Self::COLUMNS
+        ))?;
+
+        let mut challenges = Vec::new();
+        while let State::Row = statement.next()? {
+            challenges.push(Self::from_row(&statement));
+        }
+
+        Ok(challenges)
+    }
+
+    pub fn set_details(
+        db: &Database,
+        id: i64,
+        name: &str,
+        category: &str,
+        description: &str,
+    ) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "UPDATE challenges SET name = ?, category = ?, description = ? WHERE id = ?;",
+        )?;
+        statement.bind(1, name)?;
+        statement.bind(2, category)?;
+        statement.bind(3, description)?;
+        statement.bind(4, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Records the discussion thread created for a challenge.
+    pub fn set_thread_id(db: &Database, id: i64, thread_id: u64) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement =
+            connection.prepare("UPDATE challenges SET thread_id = ? WHERE id = ?;")?;
+        statement.bind(1, thread_id as i64)?;
+        statement.bind(2, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Archives a challenge, hiding it and rejecting its flags while keeping its solves.
+    pub fn archive(db: &Database, id: i64) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "UPDATE challenges SET archived_at = strftime('%s', 'now')
+            WHERE id = ? AND archived_at IS NULL;",
+        )?;
+        statement.bind(1, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+}
+
+/// A milestone of a challenge with its own flag, worth a portion of the challenge's points.
+///
+/// Every challenge has at least one stage, and is solved once all of its stages are.
+pub struct ChallengeStage {
+    pub id: i64,
+    pub challenge_id: i64,
+    pub name: String,
+    pub points: i64,
+}
+
+impl ChallengeStage {
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            challenge_id: statement.read(1).unwrap(),
+            name: statement.read(2).unwrap(),
+            points: statement.read(3).unwrap(),
+        }
+    }
+
+    /// Creates a new stage row in the database, returning the id.
+    pub fn create(
+        db: &Database,
+        challenge_id: i64,
+        name: &str,
+        flag: &str,
+        points: i64,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO challenge_stages (challenge_id, name, flag, points) VALUES (?, ?, ?, ?);",
+        )?;
+        statement.bind(1, challenge_id)?;
+        statement.bind(2, name)?;
+        statement.bind(3, flag)?;
+        statement.bind(4, points)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    /// Fetches the stages of a challenge, in the order they were added.
+    pub fn get_by_challenge(db: &Database, challenge_id: i64) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT id, challenge_id, name, points FROM challenge_stages
+            WHERE challenge_id = ? ORDER BY id",
+        )?;
+        statement.bind(1, challenge_id)?;
+
+        let mut stages = Vec::new();
+        while let State::Row = statement.next()? {
+            stages.push(Self::from_row(&statement));
+        }
+
+        Ok(stages)
+    }
+
+    /// Whether `flag` would be ambiguous as the flag of a stage or decoy: any stage, archived or not,
+    /// already has it as its flag, a stage rotated away from it still accepts it, or it is a decoy.
+    pub fn flag_in_use(db: &Database, flag: &str) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT 1 FROM challenge_stages WHERE flag = ?1
+            UNION ALL
+            SELECT 1 FROM retired_flags
+                WHERE flag = ?1 AND grace_until > strftime('%s', 'now')
+            UNION ALL
+            SELECT 1 FROM decoy_flags WHERE flag = ?1",
+        )?;
+        statement.bind(1, flag)?;
+
+        Ok(statement.next()? == State::Row)
+    }
+
+    /// Fetches a single stage of a challenge that hasn't been archived, keyed by flag.
     pub fn get_by_flag(db: &Database, flag: &str) -> Result<Option<Self>> {
         let connection = db.connection.lock()?;
 
+        let mut statement = connection.prepare(
+            "SELECT challenge_stages.id, challenge_id, challenge_stages.name, points
+            FROM challenge_stages JOIN challenges ON challenges.id = challenge_id
+            WHERE flag = ? AND archived_at IS NULL",
+        )?;
+        statement.bind(1, flag)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fetches a single stage of a challenge that hasn't been archived, keyed by a flag it was
+    /// rotated away from whose grace period hasn't yet expired.
+    pub fn get_by_grace_flag(db: &Database, flag: &str) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT challenge_stages.id, challenge_id, challenge_stages.name, points
+            FROM retired_flags
+            JOIN challenge_stages ON challenge_stages.id = retired_flags.stage_id
+            JOIN challenges ON challenges.id = challenge_id
+            WHERE retired_flags.flag = ? AND grace_until > strftime('%s', 'now')
+                AND archived_at IS NULL",
+        )?;
+        statement.bind(1, flag)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Replaces the flag of a stage, returning the old flag, or `None` if there is no stage with
+    /// that id.
+    ///
+    /// The old flag is kept in the stage's history, and is still accepted for `grace_seconds`.
+    pub fn rotate_flag(
+        db: &Database,
+        id: i64,
+        flag: &str,
+        grace_seconds: i64,
+    ) -> Result<Option<String>> {
+        let connection = db.connection.lock()?;
+
+        let mut stmt1 = connection.prepare("SELECT flag FROM challenge_stages WHERE id = ?")?;
+        stmt1.bind(1, id)?;
+
+        if stmt1.next()? == State::Done {
+            return Ok(None);
+        }
+
+        let old_flag: String = stmt1.read(0).unwrap();
+
+        // The old flag is only retired if the new one could replace it
+        transaction(&connection, || {
+            let mut stmt2 = connection.prepare(
+                "INSERT INTO retired_flags (stage_id, flag, grace_until)
+                VALUES (?, ?, strftime('%s', 'now') + ?);",
+            )?;
+            stmt2.bind(1, id)?;
+            stmt2.bind(2, old_flag.as_str())?;
+            stmt2.bind(3, grace_seconds)?;
+
+            assert_eq!(stmt2.next()?, State::Done);
+
+            let mut stmt3 =
+                connection.prepare("UPDATE challenge_stages SET flag = ? WHERE id = ?;")?;
+            stmt3.bind(1, flag)?;
+            stmt3.bind(2, id)?;
+
+            assert_eq!(stmt3.next()?, State::Done);
+
+            Ok(())
+        })?;
+
+        Ok(Some(old_flag))
+    }
+}
+
+/// A user having found the flags for every stage of a challenge.
+pub struct Solve {
+    pub challenge_id: i64,
+    /// Unix timestamp of when the last stage was found.
+    pub solved_at: i64,
+}
+
+impl Solve {
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            challenge_id: statement.read(0).unwrap(),
+            solved_at: statement.read(1).unwrap(),
+        }
+    }
+
+    pub fn get_by_user(db: &Database, user_id: u64) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT challenge_id, solved_at FROM solves WHERE user_id = ? ORDER BY solved_at",
+        )?;
+        statement.bind(1, user_id as i64)?;
+
+        let mut solves = Vec::new();
+        while let State::Row = statement.next()? {
+            solves.push(Self::from_row(&statement));
+        }
+
+        Ok(solves)
+    }
+
+    /// Fetches the ids of every user who has solved a challenge.
+    pub fn get_solver_ids(db: &Database, challenge_id: i64) -> Result<Vec<u64>> {
+        let connection = db.connection.lock()?;
+
         let mut statement =
-            connection.prepare("SELECT id, name, flag FROM challenges WHERE flag = ?")?;
+            connection.prepare("SELECT user_id FROM solves WHERE challenge_id = ?")?;
+        statement.bind(1, challenge_id)?;
+
+        let mut user_ids = Vec::new();
+        while let State::Row = statement.next()? {
+            user_ids.push(statement.read::<i64>(0).unwrap() as u64);
+        }
+
+        Ok(user_ids)
+    }
+
+    /// Fetches the ids of the challenges `user_id` was the first to solve.
+    pub fn get_first_bloods(db: &Database, user_id: u64) -> Result<Vec<i64>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT challenge_id FROM solves AS s WHERE user_id = ? AND solved_at = (
+                SELECT MIN(solved_at) FROM solves WHERE challenge_id = s.challenge_id
+                    AND user_id NOT IN (SELECT user_id FROM sanctions)
+            )",
+        )?;
+        statement.bind(1, user_id as i64)?;
+
+        let mut challenge_ids = Vec::new();
+        while let State::Row = statement.next()? {
+            challenge_ids.push(statement.read(0).unwrap());
+        }
+
+        Ok(challenge_ids)
+    }
+
+    /// Counts the solves of every challenge, keyed by challenge id.
+    pub fn get_counts(db: &Database) -> Result<HashMap<i64, i64>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT challenge_id, COUNT(*) FROM solves
+                WHERE user_id NOT IN (SELECT user_id FROM sanctions)
+                GROUP BY challenge_id",
+        )?;
+
+        let mut counts = HashMap::new();
+        while let State::Row = statement.next()? {
+            counts.insert(statement.read(0).unwrap(), statement.read(1).unwrap());
+        }
+
+        Ok(counts)
+    }
+}
+
+/// A user having found the flag for a [`ChallengeStage`].
+pub struct StageSolve;
+
+impl StageSolve {
+    /// Records that `user_id` found the flag for `stage_id`, returning `false` if they already had.
+    ///
+    /// `grace` marks solves made with a rotated flag during its grace period.
+    pub fn create(db: &Database, user_id: u64, stage_id: i64, grace: bool) -> Result<bool> {
+        let connection = db.connection.lock()?;
 
-        statement.bind(1, flag).unwrap();
+        let mut statement = connection.prepare(
+            "INSERT OR IGNORE INTO stage_solves (user_id, stage_id, grace) VALUES (?, ?, ?);",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, stage_id)?;
+        statement.bind(3, grace as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    /// Marks every stage of a challenge as solved by `user_id`, returning `false` if they had
+    /// already solved all of them.
+    pub fn award_challenge(db: &Database, user_id: u64, challenge_id: i64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT OR IGNORE INTO stage_solves (user_id, stage_id)
+            SELECT ?, id FROM challenge_stages WHERE challenge_id = ?;",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, challenge_id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    /// Removes every stage solve `user_id` has for a challenge, returning `false` if they had none.
+    pub fn revoke_challenge(db: &Database, user_id: u64, challenge_id: i64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "DELETE FROM stage_solves WHERE user_id = ? AND stage_id IN (
+                SELECT id FROM challenge_stages WHERE challenge_id = ?
+            );",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, challenge_id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    /// Fetches the ids of the stages of a challenge that `user_id` has found the flag for.
+    pub fn get_solved_stage_ids(
+        db: &Database,
+        user_id: u64,
+        challenge_id: i64,
+    ) -> Result<Vec<i64>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT stage_solves.stage_id FROM stage_solves
+            JOIN challenge_stages ON challenge_stages.id = stage_solves.stage_id
+            WHERE stage_solves.user_id = ? AND challenge_stages.challenge_id = ?",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, challenge_id)?;
+
+        let mut stage_ids = Vec::new();
+        while let State::Row = statement.next()? {
+            stage_ids.push(statement.read(0).unwrap());
+        }
+
+        Ok(stage_ids)
+    }
+}
+
+/// Returns the rowid of the last row inserted on `connection`.
+fn last_insert_rowid(connection: &Connection) -> Result<i64> {
+    let mut statement = connection.prepare("SELECT last_insert_rowid();")?;
+
+    assert_eq!(statement.next()?, State::Row);
+
+    let id: i64 = statement.read(0).unwrap();
+
+    assert_eq!(statement.next()?, State::Done);
+
+    Ok(id)
+}
+
+/// Runs `f` in a transaction on `connection`, rolling back everything it did if it fails.
+fn transaction<T>(connection: &Connection, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    connection.execute("BEGIN;")?;
+
+    match f() {
+        Ok(value) => {
+            connection.execute("COMMIT;")?;
+            Ok(value)
+        }
+        Err(why) => {
+            connection.execute("ROLLBACK;")?;
+            Err(why)
+        }
+    }
+}
+
+/// Returns the number of rows modified by the last statement run on `connection`.
+fn changes(connection: &Connection) -> Result<i64> {
+    let mut statement = connection.prepare("SELECT changes();")?;
+
+    assert_eq!(statement.next()?, State::Row);
+
+    Ok(statement.read(0).unwrap())
+}
+
+/// Key-value bot configuration, editable at runtime through `/config`.
+pub struct Setting;
+
+impl Setting {
+    /// Channel that admin notifications (e.g. decoy flag incidents) are posted to.
+    pub const ADMIN_CHANNEL: &'static str = "admin_channel";
+    /// Prefix that all flags share, e.g. `cheri` for flags of the form `cheri{...}`.
+    pub const FLAG_FORMAT: &'static str = "flag_format";
+    /// Channel that challenge solves are announced in.
+    pub const SOLVES_CHANNEL: &'static str = "solves_channel";
+    /// Bonus points for the first three solves of a challenge, as `<first>,<second>,<third>`.
+    pub const BLOOD_BONUS: &'static str = "blood_bonus";
+    /// Channel that each challenge's private discussion thread is created in.
+    pub const DISCUSSION_CHANNEL: &'static str = "discussion_channel";
+    /// Channel that the private thread of each ticket is created in.
+    pub const TICKET_CHANNEL: &'static str = "ticket_channel";
+    /// Outcomes whose responses are shown publicly rather than only to the user, as a comma
+    /// separated list of [`Setting::OUTCOME_CORRECT`], [`Setting::OUTCOME_INCORRECT`] and
+    /// [`Setting::OUTCOME_ADMIN`].
+    pub const PUBLIC_OUTCOMES: &'static str = "public_outcomes";
+
+    /// Responses to correct flag submissions.
+    pub const OUTCOME_CORRECT: &'static str = "correct";
+    /// Responses to incorrect or malformed flag submissions.
+    pub const OUTCOME_INCORRECT: &'static str = "incorrect";
+    /// Responses to admin commands.
+    pub const OUTCOME_ADMIN: &'static str = "admin";
+
+    pub fn get(db: &Database, key: &str) -> Result<Option<String>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("SELECT value FROM settings WHERE key = ?")?;
+
+        statement.bind(1, key)?;
 
         if let State::Row = statement.next()? {
-            Ok(Some(Self {
-                id: statement.read(0).unwrap(),
-                name: statement.read(1).unwrap(),
-                flag: statement.read(2).unwrap(),
-            }))
+            Ok(Some(statement.read(0).unwrap()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn set(db: &Database, key: &str, value: &str) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO settings (key, value) VALUES (?, ?)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value;",
+        )?;
+        statement.bind(1, key)?;
+        statement.bind(2, value)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    pub fn unset(db: &Database, key: &str) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM settings WHERE key = ?;")?;
+        statement.bind(1, key)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Fetches a setting holding a Discord id (channel, role, ...).
+    pub fn get_id(db: &Database, key: &str) -> Result<Option<u64>> {
+        Ok(Self::get(db, key)?.and_then(|v| v.parse().ok()))
+    }
+
+    /// Checks whether responses with the given outcome should be shown publicly.
+    pub fn is_public(db: &Database, outcome: &str) -> Result<bool> {
+        Ok(match Self::get(db, Self::PUBLIC_OUTCOMES)? {
+            Some(outcomes) => outcomes.split(',').any(|o| o == outcome),
+            None => false,
+        })
+    }
+}
+
+/// A flag that is never the answer to anything, planted to catch cheaters.
+///
+/// Submitting one looks like a wrong answer to the player, but records an [`Incident`].
+pub struct DecoyFlag {
+    pub id: i64,
+    pub flag: String,
+    pub description: String,
+    /// Points deducted from the submitter's score, `0` for no penalty.
+    pub penalty: i64,
+}
+
+impl DecoyFlag {
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            flag: statement.read(1).unwrap(),
+            description: statement.read(2).unwrap(),
+            penalty: statement.read(3).unwrap(),
+        }
+    }
+
+    /// Creates a new decoy flag row in the database, returning the id.
+    pub fn create(db: &Database, flag: &str, description: &str, penalty: i64) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection
+            .prepare("INSERT INTO decoy_flags (flag, description, penalty) VALUES (?, ?, ?);")?;
+        statement.bind(1, flag)?;
+        statement.bind(2, description)?;
+        statement.bind(3, penalty)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    /// Deletes a decoy flag, returning whether it existed. Past incidents are kept.
+    pub fn delete(db: &Database, id: i64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM decoy_flags WHERE id = ?;")?;
+        statement.bind(1, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn get_all(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection
+            .prepare("SELECT id, flag, description, penalty FROM decoy_flags ORDER BY id")?;
+
+        let mut decoys = Vec::new();
+        while let State::Row = statement.next()? {
+            decoys.push(Self::from_row(&statement));
+        }
+
+        Ok(decoys)
+    }
+
+    pub fn get_by_flag(db: &Database, flag: &str) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection
+            .prepare("SELECT id, flag, description, penalty FROM decoy_flags WHERE flag = ?")?;
+
+        statement.bind(1, flag)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
         } else {
             Ok(None)
         }
     }
 }
+
+/// A record of a user submitting a [`DecoyFlag`].
+pub struct Incident;
+
+impl Incident {
+    /// Records that `user_id` submitted `decoy`, returning the id of the incident.
+    pub fn create(db: &Database, user_id: u64, decoy: &DecoyFlag) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO incidents (user_id, decoy_id, flag, penalty) VALUES (?, ?, ?, ?);",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, decoy.id)?;
+        statement.bind(3, decoy.flag.as_str())?;
+        statement.bind(4, decoy.penalty)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+}
+
+/// A log of every well-formed flag submission, correct or not.
+pub struct Submission;
+
+impl Submission {
+    /// Records a submission of `flag` by `user_id`, where `challenge_id` is the challenge the flag
+    /// was correct for, if any.
+    pub fn create(
+        db: &Database,
+        user_id: u64,
+        flag: &str,
+        challenge_id: Option<i64>,
+    ) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection
+            .prepare("INSERT INTO submissions (user_id, flag, challenge_id) VALUES (?, ?, ?);")?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, flag)?;
+        statement.bind(3, challenge_id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+}
+
+/// A wrong answer to a challenge that players commonly submit, with a custom response, such as
+/// an intermediate value of the solution.
+pub struct KnownWrongAnswer {
+    pub id: i64,
+    pub answer: String,
+    pub message: String,
+}
+
+impl KnownWrongAnswer {
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            answer: statement.read(1).unwrap(),
+            message: statement.read(2).unwrap(),
+        }
+    }
+
+    /// Creates a new known wrong answer row in the database, returning the id.
+    pub fn create(db: &Database, challenge_id: i64, answer: &str, message: &str) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO known_wrong_answers (challenge_id, answer, message) VALUES (?, ?, ?);",
+        )?;
+        statement.bind(1, challenge_id)?;
+        statement.bind(2, answer)?;
+        statement.bind(3, message)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    /// Deletes a known wrong answer, returning whether it existed.
+    pub fn delete(db: &Database, id: i64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM known_wrong_answers WHERE id = ?;")?;
+        statement.bind(1, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn get_by_challenge(db: &Database, challenge_id: i64) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT id, answer, message FROM known_wrong_answers
+            WHERE challenge_id = ? ORDER BY id",
+        )?;
+        statement.bind(1, challenge_id)?;
+
+        let mut answers = Vec::new();
+        while let State::Row = statement.next()? {
+            answers.push(Self::from_row(&statement));
+        }
+
+        Ok(answers)
+    }
+
+    /// Fetches the known wrong answer `answer` of the challenge a submission was made for.
+    ///
+    /// Submissions not made for a specific challenge only match answers known for a single
+    /// challenge, so they are never shown the message of the wrong one.
+    pub fn get_by_answer(
+        db: &Database,
+        answer: &str,
+        challenge_id: Option<i64>,
+    ) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT id, answer, message FROM known_wrong_answers
+            WHERE answer = ?1 AND (challenge_id = ?2 OR ?2 IS NULL AND (
+                SELECT COUNT(DISTINCT challenge_id) FROM known_wrong_answers WHERE answer = ?1
+            ) = 1)
+            ORDER BY id",
+        )?;
+        statement.bind(1, answer)?;
+        statement.bind(2, challenge_id)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// A record of an admin changing something, keeping the previous value.
+pub struct AuditLog;
+
+impl AuditLog {
+    pub const CHALLENGE_RENAME: &'static str = "challenge_rename";
+    pub const CHALLENGE_RECATEGORISE: &'static str = "challenge_recategorise";
+    pub const CHALLENGE_REDESCRIBE: &'static str = "challenge_redescribe";
+    pub const CHALLENGE_ARCHIVE: &'static str = "challenge_archive";
+    pub const FLAG_ROTATE: &'static str = "flag_rotate";
+    pub const SOLVE_AWARD: &'static str = "solve_award";
+    pub const SOLVE_REVOKE: &'static str = "solve_revoke";
+    pub const SCORE_ADJUST: &'static str = "score_adjust";
+    pub const USER_SANCTION: &'static str = "user_sanction";
+    pub const USER_SANCTION_LIFT: &'static str = "user_sanction_lift";
+    /// A bot-authored message pinged everyone or some roles, recorded against the message id.
+    pub const MESSAGE_PING: &'static str = "message_ping";
+
+    /// Records that `actor_id` performed `action` on the row `target_id`, changing it from
+    /// `old_value` to `new_value`.
+    pub fn record(
+        db: &Database,
+        actor_id: u64,
+        action: &str,
+        target_id: i64,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+    ) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO audit_log (actor_id, action, target_id, old_value, new_value)
+            VALUES (?, ?, ?, ?, ?);",
+        )?;
+        statement.bind(1, actor_id as i64)?;
+        statement.bind(2, action)?;
+        statement.bind(3, target_id)?;
+        statement.bind(4, old_value)?;
+        statement.bind(5, new_value)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+}
+
+/// A disqualification or shadow ban of a user, hiding them from the scoreboard.
+pub struct Sanction {
+    pub user_id: u64,
+    pub kind: String,
+    pub reason: String,
+    pub actor_id: u64,
+    pub created_at: i64,
+}
+
+impl Sanction {
+    /// Submissions are rejected with a message saying so.
+    pub const DISQUALIFY: &'static str = "disqualify";
+    /// Submissions appear to work as normal, but are never counted.
+    pub const SHADOW_BAN: &'static str = "shadow_ban";
+
+    /// Columns read by [`Sanction::from_row`].
+    const COLUMNS: &'static str = "user_id, kind, reason, actor_id, created_at";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            user_id: statement.read::<i64>(0).unwrap() as u64,
+            kind: statement.read(1).unwrap(),
+            reason: statement.read(2).unwrap(),
+            actor_id: statement.read::<i64>(3).unwrap() as u64,
+            created_at: statement.read(4).unwrap(),
+        }
+    }
+
+    /// Sanctions `user_id`, replacing any sanction they already had.
+    pub fn set(db: &Database, user_id: u64, kind: &str, reason: &str, actor_id: u64) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT OR REPLACE INTO sanctions (user_id, kind, reason, actor_id) VALUES (?, ?, ?, ?);",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, kind)?;
+        statement.bind(3, reason)?;
+        statement.bind(4, actor_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Lifts the sanction on `user_id`, returning `false` if they had none.
+    pub fn lift(db: &Database, user_id: u64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM sanctions WHERE user_id = ?;")?;
+        statement.bind(1, user_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn get_by_user(db: &Database, user_id: u64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM sanctions WHERE user_id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, user_id as i64)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get_all(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM sanctions ORDER BY created_at",
+            Self::COLUMNS
+        ))?;
+
+        let mut sanctions = Vec::new();
+        while let State::Row = statement.next()? {
+            sanctions.push(Self::from_row(&statement));
+        }
+
+        Ok(sanctions)
+    }
+}
+
+/// A Discord role granted for solving a challenge, solving every challenge in a category, or
+/// reaching a score. Exactly one of `challenge_id`, `category` and `score` is set.
+pub struct RoleReward {
+    pub id: i64,
+    pub role_id: u64,
+    pub challenge_id: Option<i64>,
+    pub category: Option<String>,
+    pub score: Option<i64>,
+}
+
+impl RoleReward {
+    pub fn create(
+        db: &Database,
+        role_id: u64,
+        challenge_id: Option<i64>,
+        category: Option<&str>,
+        score: Option<i64>,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO role_rewards (role_id, challenge_id, category, score) VALUES (?, ?, ?, ?);",
+        )?;
+        statement.bind(1, role_id as i64)?;
+        statement.bind(2, challenge_id)?;
+        statement.bind(3, category)?;
+        statement.bind(4, score)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    /// Deletes a role reward, returning `false` if there was none with that id.
+    pub fn delete(db: &Database, id: i64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM role_rewards WHERE id = ?;")?;
+        statement.bind(1, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn get_all(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection
+            .prepare("SELECT id, role_id, challenge_id, category, score FROM role_rewards")?;
+
+        let mut rewards = Vec::new();
+        while let State::Row = statement.next()? {
+            rewards.push(Self {
+                id: statement.read(0).unwrap(),
+                role_id: statement.read::<i64>(1).unwrap() as u64,
+                challenge_id: statement.read(2).unwrap(),
+                category: statement.read(3).unwrap(),
+                score: statement.read(4).unwrap(),
+            });
+        }
+
+        Ok(rewards)
+    }
+}
+
+/// A support request from a player, discussed with admins in a private thread.
+pub struct Ticket {
+    pub id: i64,
+    pub user_id: u64,
+    pub challenge_id: Option<i64>,
+    pub description: String,
+    pub thread_id: Option<u64>,
+    pub status: String,
+    pub claimed_by: Option<u64>,
+    pub created_at: i64,
+}
+
+impl Ticket {
+    pub const OPEN: &'static str = "open";
+    pub const CLAIMED: &'static str = "claimed";
+    pub const CLOSED: &'static str = "closed";
+
+    /// Columns read by [`Ticket::from_row`].
+    const COLUMNS: &'static str =
+        "id, user_id, challenge_id, description, thread_id, status, claimed_by, created_at";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            user_id: statement.read::<i64>(1).unwrap() as u64,
+            challenge_id: statement.read(2).unwrap(),
+            description: statement.read(3).unwrap(),
+            thread_id: statement
+                .read::<Option<i64>>(4)
+                .unwrap()
+                .map(|id| id as u64),
+            status: statement.read(5).unwrap(),
+            claimed_by: statement
+                .read::<Option<i64>>(6)
+                .unwrap()
+                .map(|id| id as u64),
+            created_at: statement.read(7).unwrap(),
+        }
+    }
+
+    /// Opens a new ticket, returning its id.
+    pub fn create(
+        db: &Database,
+        user_id: u64,
+        challenge_id: Option<i64>,
+        description: &str,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO tickets (user_id, challenge_id, description) VALUES (?, ?, ?);",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, challenge_id)?;
+        statement.bind(3, description)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    pub fn set_thread_id(db: &Database, id: i64, thread_id: u64) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("UPDATE tickets SET thread_id = ? WHERE id = ?;")?;
+        statement.bind(1, thread_id as i64)?;
+        statement.bind(2, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Marks an open ticket as claimed by `admin_id`, returning `false` if it wasn't open.
+    pub fn claim(db: &Database, id: i64, admin_id: u64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "UPDATE tickets SET status = ?, claimed_by = ? WHERE id = ? AND status = ?;",
+        )?;
+        statement.bind(1, Self::CLAIMED)?;
+        statement.bind(2, admin_id as i64)?;
+        statement.bind(3, id)?;
+        statement.bind(4, Self::OPEN)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    /// Closes a ticket, returning `false` if it was already closed.
+    pub fn close(db: &Database, id: i64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "UPDATE tickets SET status = ?, closed_at = strftime('%s', 'now')
+            WHERE id = ? AND status != ?;",
+        )?;
+        statement.bind(1, Self::CLOSED)?;
+        statement.bind(2, id)?;
+        statement.bind(3, Self::CLOSED)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn delete(db: &Database, id: i64) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM tickets WHERE id = ?;")?;
+        statement.bind(1, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Stores the transcript of a closed ticket's thread.
+    pub fn set_transcript(db: &Database, id: i64, transcript: &str) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement =
+            connection.prepare("UPDATE tickets SET transcript = ? WHERE id = ?;")?;
+        statement.bind(1, transcript)?;
+        statement.bind(2, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    pub fn get_by_id(db: &Database, id: i64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM tickets WHERE id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, id)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fetches every ticket that hasn't been closed, oldest first.
+    pub fn get_unclosed(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM tickets WHERE status != ? ORDER BY id",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, Self::CLOSED)?;
+
+        let mut tickets = Vec::new();
+        while let State::Row = statement.next()? {
+            tickets.push(Self::from_row(&statement));
+        }
+
+        Ok(tickets)
+    }
+}
+
+/// A bot-authored message waiting to be sent, possibly repeatedly.
+pub struct ScheduledMessage {
+    pub id: i64,
+    pub channel_id: u64,
+    pub content: String,
+    /// Who the message may ping once sent, one of the `MENTIONS_` levels in `messages`.
+    pub mentions: String,
+    /// When the message is next sent, as a unix timestamp.
+    pub send_at: i64,
+    /// Cron expression the message repeats on, if it isn't a one-off.
+    pub cron: Option<String>,
+    pub created_by: u64,
+    /// Who last scheduled or rescheduled the message, and so is answerable for who it pings.
+    pub edited_by: u64,
+}
+
+impl ScheduledMessage {
+    /// Columns read by [`ScheduledMessage::from_row`].
+    const COLUMNS: &'static str = "id, channel_id, content, mentions, send_at, cron, created_by,
+        COALESCE(edited_by, created_by)";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            channel_id: statement.read::<i64>(1).unwrap() as u64,
+            content: statement.read(2).unwrap(),
+            mentions: statement.read(3).unwrap(),
+            send_at: statement.read(4).unwrap(),
+            cron: statement.read(5).unwrap(),
+            created_by: statement.read::<i64>(6).unwrap() as u64,
+            edited_by: statement.read::<i64>(7).unwrap() as u64,
+        }
+    }
+
+    /// `when` is the time the message is first sent and the cron expression it then repeats on,
+    /// if any.
+    pub fn create(
+        db: &Database,
+        channel_id: u64,
+        content: &str,
+        mentions: &str,
+        when: (i64, Option<&str>),
+        created_by: u64,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let (send_at, cron) = when;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO scheduled_messages
+            (channel_id, content, mentions, send_at, cron, created_by, edited_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?);",
+        )?;
+        statement.bind(1, channel_id as i64)?;
+        statement.bind(2, content)?;
+        statement.bind(3, mentions)?;
+        statement.bind(4, send_at)?;
+        statement.bind(5, cron)?;
+        statement.bind(6, created_by as i64)?;
+        statement.bind(7, created_by as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    /// Replaces everything about a scheduled message, returning `false` if there was none with
+    /// that id. `when` is as for [`ScheduledMessage::create`].
+    pub fn update(
+        db: &Database,
+        id: i64,
+        channel_id: u64,
+        content: &str,
+        mentions: &str,
+        when: (i64, Option<&str>),
+        edited_by: u64,
+    ) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let (send_at, cron) = when;
+
+        let mut statement = connection.prepare(
+            "UPDATE scheduled_messages
+            SET channel_id = ?, content = ?, mentions = ?, send_at = ?, cron = ?, edited_by = ?
+            WHERE id = ?;",
+        )?;
+        statement.bind(1, channel_id as i64)?;
+        statement.bind(2, content)?;
+        statement.bind(3, mentions)?;
+        statement.bind(4, send_at)?;
+        statement.bind(5, cron)?;
+        statement.bind(6, edited_by as i64)?;
+        statement.bind(7, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn set_send_at(db: &Database, id: i64, send_at: i64) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement =
+            connection.prepare("UPDATE scheduled_messages SET send_at = ? WHERE id = ?;")?;
+        statement.bind(1, send_at)?;
+        statement.bind(2, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Deletes a scheduled message, returning `false` if there was none with that id.
+    pub fn delete(db: &Database, id: i64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM scheduled_messages WHERE id = ?;")?;
+        statement.bind(1, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn get_by_id(db: &Database, id: i64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM scheduled_messages WHERE id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, id)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fetches every scheduled message, soonest first.
+    pub fn get_all(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM scheduled_messages ORDER BY send_at",
+            Self::COLUMNS
+        ))?;
+
+        let mut messages = Vec::new();
+        while let State::Row = statement.next()? {
+            messages.push(Self::from_row(&statement));
+        }
+
+        Ok(messages)
+    }
+
+    /// Fetches every scheduled message that should have been sent by now.
+    pub fn get_due(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM scheduled_messages WHERE send_at <= strftime('%s', 'now')
+            ORDER BY send_at",
+            Self::COLUMNS
+        ))?;
+
+        let mut messages = Vec::new();
+        while let State::Row = statement.next()? {
+            messages.push(Self::from_row(&statement));
+        }
+
+        Ok(messages)
+    }
+}
+
+/// A message sent by the bot on behalf of an admin, registered so it can be edited by name.
+pub struct BotMessage {
+    pub id: i64,
+    pub name: String,
+    pub channel_id: u64,
+    pub message_id: u64,
+    pub created_by: u64,
+    pub created_at: i64,
+    /// The kind of live data the message shows and is kept up to date with, if any.
+    pub live: Option<String>,
+    /// Extra settings of the live message, depending on its kind.
+    pub live_argument: Option<String>,
+    /// The message JSON the live message was last updated to.
+    pub live_json: Option<String>,
+    /// The [`SelfRole`] group offered under the message, if any.
+    pub role_group: Option<String>,
+    /// How the roles of `role_group` are offered, as buttons or a select menu.
+    pub role_style: Option<String>,
+}
+
+impl BotMessage {
+    pub const LIVE_SCOREBOARD: &'static str = "scoreboard";
+    pub const LIVE_CHALLENGES: &'static str = "challenges";
+    /// Takes the argument `<unix timestamp>:<title>`.
+    pub const LIVE_COUNTDOWN: &'static str = "countdown";
+
+    pub const ROLES_BUTTONS: &'static str = "buttons";
+    pub const ROLES_SELECT: &'static str = "select";
+
+    /// Columns read by [`BotMessage::from_row`].
+    const COLUMNS: &'static str = "id, name, channel_id, message_id, created_by, created_at, \
+        live, live_argument, live_json, role_group, role_style";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            name: statement.read(1).unwrap(),
+            channel_id: statement.read::<i64>(2).unwrap() as u64,
+            message_id: statement.read::<i64>(3).unwrap() as u64,
+            created_by: statement.read::<i64>(4).unwrap() as u64,
+            created_at: statement.read(5).unwrap(),
+            live: statement.read(6).unwrap(),
+            live_argument: statement.read(7).unwrap(),
+            live_json: statement.read(8).unwrap(),
+            role_group: statement.read(9).unwrap(),
+            role_style: statement.read(10).unwrap(),
+        }
+    }
+
+    /// Registers a message, named `message-<id>` if no name is given.
+    pub fn create(
+        db: &Database,
+        name: Option<&str>,
+        channel_id: u64,
+        message_id: u64,
+        created_by: u64,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO bot_messages (name, channel_id, message_id, created_by)
+            VALUES (?, ?, ?, ?);",
+        )?;
+        statement.bind(1, name)?;
+        statement.bind(2, channel_id as i64)?;
+        statement.bind(3, message_id as i64)?;
+        statement.bind(4, created_by as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        let id = last_insert_rowid(&connection)?;
+
+        if name.is_none() {
+            let mut statement =
+                connection.prepare("UPDATE bot_messages SET name = ? WHERE id = ?;")?;
+            statement.bind(1, format!("message-{}", id).as_str())?;
+            statement.bind(2, id)?;
+
+            assert_eq!(statement.next()?, State::Done);
+        }
+
+        Ok(id)
+    }
+
+    pub fn get_by_name(db: &Database, name: &str) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM bot_messages WHERE name = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, name)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get_by_message_id(db: &Database, message_id: u64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM bot_messages WHERE message_id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, message_id as i64)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Makes a message show `live` data, or stops it being updated if `live` is `None`.
+    pub fn set_live(
+        db: &Database,
+        id: i64,
+        live: Option<&str>,
+        live_argument: Option<&str>,
+    ) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "UPDATE bot_messages SET live = ?, live_argument = ?, live_json = NULL WHERE id = ?;",
+        )?;
+        statement.bind(1, live)?;
+        statement.bind(2, live_argument)?;
+        statement.bind(3, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn set_live_json(db: &Database, id: i64, live_json: &str) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement =
+            connection.prepare("UPDATE bot_messages SET live_json = ? WHERE id = ?;")?;
+        statement.bind(1, live_json)?;
+        statement.bind(2, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Offers the roles of a [`SelfRole`] group under a message, or stops offering any if `group`
+    /// is `None`.
+    pub fn set_role_menu(
+        db: &Database,
+        id: i64,
+        group: Option<&str>,
+        style: Option<&str>,
+    ) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection
+            .prepare("UPDATE bot_messages SET role_group = ?, role_style = ? WHERE id = ?;")?;
+        statement.bind(1, group)?;
+        statement.bind(2, style)?;
+        statement.bind(3, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    /// Fetches every message offering the roles of a [`SelfRole`] group.
+    pub fn get_by_role_group(db: &Database, role_group: &str) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM bot_messages WHERE role_group = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, role_group)?;
+
+        let mut messages = Vec::new();
+        while let State::Row = statement.next()? {
+            messages.push(Self::from_row(&statement));
+        }
+
+        Ok(messages)
+    }
+
+    /// Fetches every message showing live data.
+    pub fn get_live(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM bot_messages WHERE live IS NOT NULL",
+            Self::COLUMNS
+        ))?;
+
+        let mut messages = Vec::new();
+        while let State::Row = statement.next()? {
+            messages.push(Self::from_row(&statement));
+        }
+
+        Ok(messages)
+    }
+
+    /// Fetches every registered message, by name.
+    pub fn get_all(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM bot_messages ORDER BY name",
+            Self::COLUMNS
+        ))?;
+
+        let mut messages = Vec::new();
+        while let State::Row = statement.next()? {
+            messages.push(Self::from_row(&statement));
+        }
+
+        Ok(messages)
+    }
+}
+
+/// A version of a [`BotMessage`], stored as Discord message JSON each time it is sent or edited.
+pub struct BotMessageRevision {
+    pub id: i64,
+    pub bot_message_id: i64,
+    pub json: String,
+    pub actor_id: u64,
+    pub created_at: i64,
+}
+
+impl BotMessageRevision {
+    /// Columns read by [`BotMessageRevision::from_row`].
+    const COLUMNS: &'static str = "id, bot_message_id, json, actor_id, created_at";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            id: statement.read(0).unwrap(),
+            bot_message_id: statement.read(1).unwrap(),
+            json: statement.read(2).unwrap(),
+            actor_id: statement.read::<i64>(3).unwrap() as u64,
+            created_at: statement.read(4).unwrap(),
+        }
+    }
+
+    pub fn create(db: &Database, bot_message_id: i64, json: &str, actor_id: u64) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO bot_message_revisions (bot_message_id, json, actor_id)
+            VALUES (?, ?, ?);",
+        )?;
+        statement.bind(1, bot_message_id)?;
+        statement.bind(2, json)?;
+        statement.bind(3, actor_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    pub fn get_by_id(db: &Database, id: i64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM bot_message_revisions WHERE id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, id)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fetches every revision of a message, newest first.
+    pub fn get_by_message(db: &Database, bot_message_id: i64) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM bot_message_revisions WHERE bot_message_id = ? ORDER BY id DESC",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, bot_message_id)?;
+
+        let mut revisions = Vec::new();
+        while let State::Row = statement.next()? {
+            revisions.push(Self::from_row(&statement));
+        }
+
+        Ok(revisions)
+    }
+}
+
+/// A role members may give themselves through the buttons or select menu under a bot message.
+pub struct SelfRole {
+    pub role_id: u64,
+    /// Roles are offered together by group.
+    pub role_group: String,
+    pub label: String,
+    pub actor_id: u64,
+}
+
+impl SelfRole {
+    /// Columns read by [`SelfRole::from_row`].
+    const COLUMNS: &'static str = "role_id, role_group, label, actor_id";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            role_id: statement.read::<i64>(0).unwrap() as u64,
+            role_group: statement.read(1).unwrap(),
+            label: statement.read(2).unwrap(),
+            actor_id: statement.read::<i64>(3).unwrap() as u64,
+        }
+    }
+
+    /// Allows members to give themselves a role, replacing its group and label if it was already
+    /// allowed.
+    pub fn set(
+        db: &Database,
+        role_id: u64,
+        role_group: &str,
+        label: &str,
+        actor_id: u64,
+    ) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT OR REPLACE INTO self_roles (role_id, role_group, label, actor_id)
+            VALUES (?, ?, ?, ?);",
+        )?;
+        statement.bind(1, role_id as i64)?;
+        statement.bind(2, role_group)?;
+        statement.bind(3, label)?;
+        statement.bind(4, actor_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Stops members giving themselves a role, returning `false` if they couldn't already.
+    pub fn delete(db: &Database, role_id: u64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare("DELETE FROM self_roles WHERE role_id = ?;")?;
+        statement.bind(1, role_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    pub fn get_by_id(db: &Database, role_id: u64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM self_roles WHERE role_id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, role_id as i64)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fetches the roles of a group, by label.
+    pub fn get_by_group(db: &Database, role_group: &str) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM self_roles WHERE role_group = ? ORDER BY label",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, role_group)?;
+
+        let mut roles = Vec::new();
+        while let State::Row = statement.next()? {
+            roles.push(Self::from_row(&statement));
+        }
+
+        Ok(roles)
+    }
+
+    /// Fetches every allowed role, by group and label.
+    pub fn get_all(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM self_roles ORDER BY role_group, label",
+            Self::COLUMNS
+        ))?;
+
+        let mut roles = Vec::new();
+        while let State::Row = statement.next()? {
+            roles.push(Self::from_row(&statement));
+        }
+
+        Ok(roles)
+    }
+
+    /// Sets whether members may hold at most one role of a group at a time.
+    pub fn set_exclusive(db: &Database, role_group: &str, exclusive: bool) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(if exclusive {
+            "INSERT OR IGNORE INTO exclusive_role_groups (role_group) VALUES (?);"
+        } else {
+            "DELETE FROM exclusive_role_groups WHERE role_group = ?;"
+        })?;
+        statement.bind(1, role_group)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    pub fn is_exclusive(db: &Database, role_group: &str) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement =
+            connection.prepare("SELECT 1 FROM exclusive_role_groups WHERE role_group = ?")?;
+        statement.bind(1, role_group)?;
+
+        Ok(statement.next()? == State::Row)
+    }
+}
+
+/// A manual bonus or penalty applied to a user's score by an admin.
+pub struct ScoreAdjustment {
+    pub id: i64,
+    pub points: i64,
+    pub reason: String,
+    pub actor_id: u64,
+    pub created_at: i64,
+}
+
+impl ScoreAdjustment {
+    /// Records that `actor_id` adjusted the score of `user_id` by `points`, returning the id of
+    /// the new ledger entry.
+    pub fn create(
+        db: &Database,
+        user_id: u64,
+        points: i64,
+        reason: &str,
+        actor_id: u64,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO score_adjustments (user_id, points, reason, actor_id) VALUES (?, ?, ?, ?);",
+        )?;
+        statement.bind(1, user_id as i64)?;
+        statement.bind(2, points)?;
+        statement.bind(3, reason)?;
+        statement.bind(4, actor_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    /// Fetches every adjustment made to the score of `user_id`, oldest first.
+    pub fn get_by_user(db: &Database, user_id: u64) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT id, points, reason, actor_id, created_at FROM score_adjustments
+            WHERE user_id = ? ORDER BY created_at, id",
+        )?;
+        statement.bind(1, user_id as i64)?;
+
+        let mut adjustments = Vec::new();
+        while let State::Row = statement.next()? {
+            adjustments.push(Self {
+                id: statement.read(0).unwrap(),
+                points: statement.read(1).unwrap(),
+                reason: statement.read(2).unwrap(),
+                actor_id: statement.read::<i64>(3).unwrap() as u64,
+                created_at: statement.read(4).unwrap(),
+            });
+        }
+
+        Ok(adjustments)
+    }
+}
+
+/// A user's standing in the competition.
+pub struct Score {
+    pub user_id: u64,
+    pub points: i64,
+    /// Unix timestamp of the user's most recent solve, if they have any.
+    pub last_solve_at: Option<i64>,
+}
+
+impl Score {
+    /// Fetches the scores of every user who has scored or been penalised, ranked from first to
+    /// last. Sanctioned users are left out.
+    ///
+    /// Ties are broken by whoever reached their score first.
+    pub fn get_scoreboard(db: &Database) -> Result<Vec<Self>> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "SELECT user_id, SUM(points) AS total, MAX(solved_at) AS last_solve_at FROM (
+                SELECT stage_solves.user_id, challenge_stages.points, stage_solves.solved_at
+                    FROM stage_solves
+                    JOIN challenge_stages ON challenge_stages.id = stage_solves.stage_id
+                UNION ALL
+                SELECT user_id, -penalty, NULL FROM incidents
+                UNION ALL
+                SELECT user_id, points, NULL FROM score_adjustments
+            )
+            WHERE user_id NOT IN (SELECT user_id FROM sanctions)
+            GROUP BY user_id
+            ORDER BY total DESC, last_solve_at IS NULL, last_solve_at ASC",
+        )?;
+
+        let mut scores = Vec::new();
+        while let State::Row = statement.next()? {
+            scores.push(Self {
+                user_id: statement.read::<i64>(0).unwrap() as u64,
+                points: statement.read(1).unwrap(),
+                last_solve_at: statement.read(2).unwrap(),
+            });
+        }
+
+        Ok(scores)
+    }
+}
+
+/// An announcement channel whose new bot-authored messages are published to following servers.
+pub struct CrosspostChannel;
+
+impl CrosspostChannel {
+    pub fn set(db: &Database, channel_id: u64, enabled: bool) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(if enabled {
+            "INSERT OR IGNORE INTO crosspost_channels (channel_id) VALUES (?);"
+        } else {
+            "DELETE FROM crosspost_channels WHERE channel_id = ?;"
+        })?;
+        statement.bind(1, channel_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    pub fn is_enabled(db: &Database, channel_id: u64) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        let mut statement =
+            connection.prepare("SELECT 1 FROM crosspost_channels WHERE channel_id = ?")?;
+        statement.bind(1, channel_id as i64)?;
+
+        Ok(statement.next()? == State::Row)
+    }
+}
+
+/// A bot-authored message previewed to an admin, kept until they confirm or cancel sending it, or
+/// for a day at most.
+pub struct PendingDraft {
+    /// How the draft was authored, so it can be reopened for editing the same way.
+    pub kind: String,
+    pub channel_id: u64,
+    /// The message the draft overwrites, or `None` if it is sent as a new message.
+    pub message_id: Option<u64>,
+    /// The name to register a new message as.
+    pub name: Option<String>,
+    pub json: String,
+    /// Who the message may ping once sent, one of the `MENTIONS_` levels in `messages`.
+    pub mentions: String,
+    /// When a scheduled draft is first sent, as a unix timestamp.
+    pub send_at: Option<i64>,
+    /// Cron expression a scheduled draft repeats on, if it isn't a one-off.
+    pub cron: Option<String>,
+    /// The scheduled message a scheduled draft replaces, or `None` if it is scheduled anew.
+    pub scheduled_id: Option<i64>,
+}
+
+impl PendingDraft {
+    pub const SEND: &'static str = "send";
+    pub const EDIT: &'static str = "edit";
+    pub const EMBED: &'static str = "embed";
+    pub const JSON: &'static str = "json";
+    /// Scheduled to be sent later rather than sent once confirmed.
+    pub const SCHEDULE: &'static str = "schedule";
+
+    /// How long a draft is kept without being confirmed or cancelled, in seconds.
+    const LIFETIME: i64 = 24 * 60 * 60;
+
+    /// Columns read by [`PendingDraft::from_row`].
+    const COLUMNS: &'static str =
+        "kind, channel_id, message_id, name, json, mentions, send_at, cron, scheduled_id";
+
+    fn from_row(statement: &Statement) -> Self {
+        Self {
+            kind: statement.read(0).unwrap(),
+            channel_id: statement.read::<i64>(1).unwrap() as u64,
+            message_id: statement
+                .read::<Option<i64>>(2)
+                .unwrap()
+                .map(|id| id as u64),
+            name: statement.read(3).unwrap(),
+            json: statement.read(4).unwrap(),
+            mentions: statement.read(5).unwrap(),
+            send_at: statement.read(6).unwrap(),
+            cron: statement.read(7).unwrap(),
+            scheduled_id: statement.read(8).unwrap(),
+        }
+    }
+
+    /// Deletes every draft that has been left unconfirmed for too long, so it can't be acted on
+    /// any more.
+    fn forget_expired(connection: &Connection) -> Result<()> {
+        let mut statement = connection
+            .prepare("DELETE FROM pending_drafts WHERE created_at < strftime('%s', 'now') - ?;")?;
+        statement.bind(1, Self::LIFETIME)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    /// Stores a draft.
+    pub fn create(
+        db: &Database,
+        kind: &str,
+        user_id: u64,
+        channel_id: u64,
+        message_id: Option<u64>,
+        name: Option<&str>,
+        json: &str,
+    ) -> Result<i64> {
+        let connection = db.connection.lock()?;
+
+        Self::forget_expired(&connection)?;
+
+        let mut statement = connection.prepare(
+            "INSERT INTO pending_drafts (kind, user_id, channel_id, message_id, name, json)
+            VALUES (?, ?, ?, ?, ?, ?);",
+        )?;
+        statement.bind(1, kind)?;
+        statement.bind(2, user_id as i64)?;
+        statement.bind(3, channel_id as i64)?;
+        statement.bind(4, message_id.map(|id| id as i64))?;
+        statement.bind(5, name)?;
+        statement.bind(6, json)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        last_insert_rowid(&connection)
+    }
+
+    pub fn get(db: &Database, id: i64, user_id: u64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        Self::forget_expired(&connection)?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM pending_drafts WHERE id = ? AND user_id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, id)?;
+        statement.bind(2, user_id as i64)?;
+
+        if let State::Row = statement.next()? {
+            Ok(Some(Self::from_row(&statement)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Sets when a draft of kind [`PendingDraft::SCHEDULE`] is sent, and which scheduled message
+    /// it replaces, if any.
+    pub fn set_schedule(
+        db: &Database,
+        id: i64,
+        send_at: i64,
+        cron: Option<&str>,
+        scheduled_id: Option<i64>,
+    ) -> Result<()> {
+        let connection = db.connection.lock()?;
+
+        let mut statement = connection.prepare(
+            "UPDATE pending_drafts SET send_at = ?, cron = ?, scheduled_id = ? WHERE id = ?;",
+        )?;
+        statement.bind(1, send_at)?;
+        statement.bind(2, cron)?;
+        statement.bind(3, scheduled_id)?;
+        statement.bind(4, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(())
+    }
+
+    pub fn set_mentions(db: &Database, id: i64, user_id: u64, mentions: &str) -> Result<bool> {
+        let connection = db.connection.lock()?;
+
+        Self::forget_expired(&connection)?;
+
+        let mut statement = connection
+            .prepare("UPDATE pending_drafts SET mentions = ? WHERE id = ? AND user_id = ?;")?;
+        statement.bind(1, mentions)?;
+        statement.bind(2, id)?;
+        statement.bind(3, user_id as i64)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(changes(&connection)? > 0)
+    }
+
+    /// Removes a draft by the given user and returns it, so it can be acted on only once.
+    pub fn take(db: &Database, id: i64, user_id: u64) -> Result<Option<Self>> {
+        let connection = db.connection.lock()?;
+
+        Self::forget_expired(&connection)?;
+
+        let mut statement = connection.prepare(format!(
+            "SELECT {} FROM pending_drafts WHERE id = ? AND user_id = ?",
+            Self::COLUMNS
+        ))?;
+        statement.bind(1, id)?;
+        statement.bind(2, user_id as i64)?;
+
+        let draft = match statement.next()? {
+            State::Row => Self::from_row(&statement),
+            State::Done => return Ok(None),
+        };
+
+        let mut statement = connection.prepare("DELETE FROM pending_drafts WHERE id = ?;")?;
+        statement.bind(1, id)?;
+
+        assert_eq!(statement.next()?, State::Done);
+
+        Ok(Some(draft))
+    }
+}
@@ -0,0 +1,248 @@
+use serenity::{
+    client::Context,
+    model::{
+        id::{ChannelId, RoleId},
+        interactions::{
+            application_command::ApplicationCommandInteraction,
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        user::User,
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{ChallengeStage, DecoyFlag, Incident, Setting},
+        Database,
+    },
+    get_option, truncate, InteractionError, InteractionResult,
+};
+
+/// How much of each decoy's flag is shown when listing them, so that many fit.
+const MAX_LISTED_FLAG_LENGTH: usize = 40;
+/// How much of each decoy's description is shown when listing them.
+const MAX_LISTED_DESCRIPTION_LENGTH: usize = 100;
+
+pub async fn cmd_decoy(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    match command.data.options.get(0).map(|o| o.name.as_str()) {
+        Some("add") => cmd_decoy_add(ctx, db, command).await,
+        Some("list") => cmd_decoy_list(ctx, db, command).await,
+        Some("remove") => cmd_decoy_remove(ctx, db, command).await,
+        _ => Err(InteractionError::UnprocessableRequest),
+    }
+}
+
+async fn cmd_decoy_add(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let flag = get_option(options, "flag")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let description = get_option(options, "description")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let penalty = get_option(options, "penalty")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0);
+
+    // A decoy sharing its flag with a stage would penalise whoever solves that stage
+    let problem = if penalty < 0 {
+        Some("Penalties can't be negative!")
+    } else if ChallengeStage::flag_in_use(db, flag)? {
+        Some("That flag is already in use by a stage or another decoy!")
+    } else {
+        None
+    };
+
+    // Decoy flags are secret, so keep everything about them ephemeral
+    if let Some(problem) = problem {
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|data| {
+                        data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                            .embed(|embed| embed.title(problem))
+                    })
+            })
+            .await?;
+
+        return Ok(());
+    }
+
+    let id = DecoyFlag::create(db, flag, description, penalty)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| {
+                            embed
+                                .title("Added decoy flag successfully!")
+                                .field("Id", id, true)
+                                .field("Penalty", penalty, true)
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn cmd_decoy_list(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let decoys = DecoyFlag::get_all(db)?;
+
+    let description = if decoys.is_empty() {
+        "No decoy flags registered.".to_string()
+    } else {
+        decoys
+            .iter()
+            .map(|decoy| {
+                format!(
+                    "`{}` ||`{}`|| (penalty {}): {}",
+                    decoy.id,
+                    truncate(&decoy.flag, MAX_LISTED_FLAG_LENGTH),
+                    decoy.penalty,
+                    truncate(&decoy.description, MAX_LISTED_DESCRIPTION_LENGTH)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| {
+                            embed
+                                .title("Decoy flags")
+                                .description(truncate(&description, 4096))
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn cmd_decoy_remove(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "id"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let title = if DecoyFlag::delete(db, id)? {
+        "Removed decoy flag successfully!"
+    } else {
+        "No decoy flag with that id!"
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| embed.title(title))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Records an incident for `user` submitting `decoy`.
+///
+/// This must not change anything the player sees; the caller is expected to respond exactly as it
+/// would to a wrong flag.
+pub fn record_decoy_submission(db: &Database, user: &User, decoy: &DecoyFlag) -> InteractionResult {
+    println!(
+        "Decoy flag {} submitted by {} ({})",
+        decoy.id,
+        user.tag(),
+        user.id
+    );
+
+    Incident::create(db, user.id.0, decoy)?;
+
+    Ok(())
+}
+
+/// Notifies admins in the configured admin channel that `user` submitted `decoy`.
+pub async fn notify_decoy_submission(
+    ctx: &Context,
+    db: &Database,
+    user: &User,
+    decoy: &DecoyFlag,
+) -> InteractionResult {
+    let channel_id = match Setting::get_id(db, Setting::ADMIN_CHANNEL)? {
+        Some(channel_id) => channel_id,
+        None => return Ok(()),
+    };
+
+    ChannelId(channel_id)
+        .send_message(&ctx.http, |message| {
+            message.embed(|embed| {
+                embed
+                    .title("Decoy flag submitted")
+                    .colour((255, 165, 0))
+                    .description(format!(
+                        "{} submitted decoy flag `{}`: {}",
+                        user.mention(),
+                        decoy.id,
+                        decoy.description
+                    ))
+                    .field("Penalty applied", decoy.penalty, true)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use serenity::{
+    client::Context,
+    model::id::{ChannelId, UserId},
+};
+
+use crate::{
+    db::{
+        models::{Challenge, Setting, Solve},
+        Database,
+    },
+    truncate, InteractionError, InteractionResult,
+};
+
+/// Creates the private discussion thread for a challenge in the discussion channel and adds
+/// everyone who has already solved it, returning the thread, or `None` if there is no discussion
+/// channel.
+pub async fn create_thread(
+    ctx: &Context,
+    db: &Database,
+    challenge: &Challenge,
+) -> Result<Option<ChannelId>, InteractionError> {
+    let channel_id = match Setting::get_id(db, Setting::DISCUSSION_CHANNEL)? {
+        Some(channel_id) => ChannelId(channel_id),
+        None => return Ok(None),
+    };
+
+    let thread = channel_id
+        .create_private_thread(&ctx.http, |thread| {
+            thread
+                .name(truncate(&format!("💬 {}", challenge.name), 100))
+                .auto_archive_duration(1440)
+        })
+        .await?;
+
+    Challenge::set_thread_id(db, challenge.id, thread.id.0)?;
+
+    thread
+        .id
+        .send_message(&ctx.http, |message| {
+            message.content(format!(
+                "Discussion for **{}**. Only players who have solved it can see this thread, so \
+                spoilers are fine here!",
+                challenge.name
+            ))
+        })
+        .await?;
+
+    for user_id in Solve::get_solver_ids(db, challenge.id)? {
+        thread
+            .id
+            .add_thread_member(&ctx.http, UserId(user_id))
+            .await?;
+    }
+
+    Ok(Some(thread.id))
+}
+
+/// Gives `user_id` access to the discussion thread of a challenge they solved, if it has one.
+pub async fn add_solver(
+    ctx: &Context,
+    challenge: &Challenge,
+    user_id: UserId,
+) -> InteractionResult {
+    if let Some(thread_id) = challenge.thread_id {
+        // Members can't be added to archived threads
+        ChannelId(thread_id)
+            .edit_thread(&ctx.http, |thread| thread.archived(false))
+            .await?;
+        ChannelId(thread_id)
+            .add_thread_member(&ctx.http, user_id)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Takes away access to the discussion thread of a challenge, e.g. when a solve is revoked.
+pub async fn remove_solver(
+    ctx: &Context,
+    challenge: &Challenge,
+    user_id: UserId,
+) -> InteractionResult {
+    if let Some(thread_id) = challenge.thread_id {
+        ChannelId(thread_id)
+            .edit_thread(&ctx.http, |thread| thread.archived(false))
+            .await?;
+        ChannelId(thread_id)
+            .remove_thread_member(&ctx.http, user_id)
+            .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,659 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    model::{
+        channel::Message,
+        interactions::message_component::{ActionRowComponent, ButtonStyle},
+    },
+};
+
+use crate::truncate;
+
+// Discord's limits on messages, see
+// https://discord.com/developers/docs/resources/channel#embed-object-embed-limits
+const MAX_CONTENT_LENGTH: usize = 2000;
+const MAX_EMBEDS: usize = 10;
+const MAX_TITLE_LENGTH: usize = 256;
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+const MAX_FIELDS: usize = 25;
+const MAX_FIELD_NAME_LENGTH: usize = 256;
+pub const MAX_FIELD_VALUE_LENGTH: usize = 1024;
+const MAX_FOOTER_LENGTH: usize = 2048;
+const MAX_AUTHOR_LENGTH: usize = 256;
+pub const MAX_EMBEDS_TOTAL_LENGTH: usize = 6000;
+const MAX_ACTION_ROWS: usize = 5;
+const MAX_BUTTONS_PER_ROW: usize = 5;
+const MAX_BUTTON_LABEL_LENGTH: usize = 80;
+const MAX_POST_TITLE_LENGTH: usize = 100;
+
+/// Component type numbers used in Discord's message JSON.
+const COMPONENT_ACTION_ROW: u8 = 1;
+const COMPONENT_BUTTON: u8 = 2;
+const BUTTON_STYLE_LINK: u8 = 5;
+
+/// A bot-authored message as it is written in Discord's message JSON, holding only the parts
+/// admins can author: content, embeds and link buttons.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MessageDraft {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<EmbedDraft>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ActionRowDraft>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EmbedDraft {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<AuthorDraft>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldDraft>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<FooterDraft>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageDraft>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<ImageDraft>,
+    /// ISO 8601 timestamp shown next to the footer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuthorDraft {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FieldDraft {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FooterDraft {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ImageDraft {
+    pub url: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActionRowDraft {
+    #[serde(rename = "type", default = "action_row_type")]
+    pub kind: u8,
+    #[serde(default)]
+    pub components: Vec<ButtonDraft>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ButtonDraft {
+    #[serde(rename = "type", default = "button_type")]
+    pub kind: u8,
+    #[serde(default = "link_style")]
+    pub style: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+fn action_row_type() -> u8 {
+    COMPONENT_ACTION_ROW
+}
+
+fn button_type() -> u8 {
+    COMPONENT_BUTTON
+}
+
+fn link_style() -> u8 {
+    BUTTON_STYLE_LINK
+}
+
+impl MessageDraft {
+    /// Parses a draft from Discord's message JSON, checking it against Discord's limits.
+    ///
+    /// Also accepts the share format of Discohook, `{"messages": [{"data": <message>}]}`, as long
+    /// as it holds a single message.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut value: Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+        if let Some(messages) = value.get_mut("messages").and_then(Value::as_array_mut) {
+            if messages.len() != 1 {
+                return Err(format!(
+                    "Expected a single message but found {}, import them one at a time!",
+                    messages.len()
+                ));
+            }
+            value = messages[0]
+                .get_mut("data")
+                .map(Value::take)
+                .ok_or_else(|| "Discohook message is missing its `data`!".to_string())?;
+        }
+
+        let draft: Self =
+            serde_json::from_value(value).map_err(|e| format!("Invalid message: {}", e))?;
+        draft.validate()?;
+        Ok(draft)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// A title for a forum post starting with the message: the title of its first embed, or else
+    /// the first line of its content or of its first embed's description.
+    pub fn post_title(&self) -> Option<String> {
+        let embed = self.embeds.get(0);
+
+        embed
+            .and_then(|embed| embed.title.as_deref())
+            .into_iter()
+            .chain(
+                self.content
+                    .as_deref()
+                    .and_then(|content| content.lines().next()),
+            )
+            .chain(embed.and_then(|embed| embed.description.as_deref()?.lines().next()))
+            .map(str::trim)
+            .find(|title| !title.is_empty())
+            .map(|title| truncate(title, MAX_POST_TITLE_LENGTH))
+    }
+
+    /// Builds a draft from the guided embed modal, replacing the first embed and the link buttons
+    /// of `self` and keeping everything else.
+    ///
+    /// Fields are given one per line as `name | value`, optionally followed by `| inline`, buttons
+    /// one per line as `label | url`, and the remaining style one per line as `key: value` with
+    /// the keys `colour`, `url`, `author`, `footer`, `image` and `thumbnail`.
+    pub fn with_form(
+        mut self,
+        title: &str,
+        description: &str,
+        fields: &str,
+        buttons: &str,
+        style: &str,
+    ) -> Result<Self, String> {
+        let mut embed = EmbedDraft {
+            title: non_empty(title),
+            description: non_empty(description),
+            ..Default::default()
+        };
+
+        for (i, line) in lines(fields) {
+            let mut parts = line.splitn(3, '|').map(str::trim);
+            let (name, value) = match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) if !name.is_empty() && !value.is_empty() => (name, value),
+                _ => return Err(format!("Field on line {} should be `name | value`!", i)),
+            };
+            let inline = match parts.next() {
+                None => false,
+                Some("inline") => true,
+                Some(other) => {
+                    return Err(format!(
+                        "Field on line {} ends in `{}`, expected `inline`!",
+                        i, other
+                    ))
+                }
+            };
+
+            embed.fields.push(FieldDraft {
+                name: name.to_string(),
+                value: value.to_string(),
+                inline,
+            });
+        }
+
+        for (i, line) in lines(style) {
+            let (key, value) = line
+                .split_once(':')
+                .map(|(key, value)| (key.trim().to_lowercase(), value.trim()))
+                .ok_or_else(|| format!("Style on line {} should be `key: value`!", i))?;
+
+            match key.as_str() {
+                "colour" | "color" => embed.color = Some(parse_colour(value)?),
+                "url" => embed.url = Some(value.to_string()),
+                // The form has no room for icons, so those of the embed being edited are kept
+                "author" => {
+                    let old = self.embeds.get(0).and_then(|e| e.author.as_ref());
+                    embed.author = Some(AuthorDraft {
+                        name: value.to_string(),
+                        url: old.and_then(|a| a.url.clone()),
+                        icon_url: old.and_then(|a| a.icon_url.clone()),
+                    })
+                }
+                "footer" => {
+                    let old = self.embeds.get(0).and_then(|e| e.footer.as_ref());
+                    embed.footer = Some(FooterDraft {
+                        text: value.to_string(),
+                        icon_url: old.and_then(|f| f.icon_url.clone()),
+                    })
+                }
+                "image" => {
+                    embed.image = Some(ImageDraft {
+                        url: value.to_string(),
+                    })
+                }
+                "thumbnail" => {
+                    embed.thumbnail = Some(ImageDraft {
+                        url: value.to_string(),
+                    })
+                }
+                _ => return Err(format!("Unknown style `{}` on line {}!", key, i)),
+            }
+        }
+
+        let mut links = Vec::new();
+        for (i, line) in lines(buttons) {
+            match line.split_once('|') {
+                Some((label, url)) if !label.trim().is_empty() => links.push(ButtonDraft {
+                    kind: COMPONENT_BUTTON,
+                    style: BUTTON_STYLE_LINK,
+                    label: Some(label.trim().to_string()),
+                    url: Some(url.trim().to_string()),
+                }),
+                _ => return Err(format!("Button on line {} should be `label | url`!", i)),
+            }
+        }
+
+        if self.embeds.is_empty() {
+            self.embeds.push(embed);
+        } else {
+            self.embeds[0] = embed;
+        }
+
+        self.components = links
+            .chunks(MAX_BUTTONS_PER_ROW)
+            .map(|row| ActionRowDraft {
+                kind: COMPONENT_ACTION_ROW,
+                components: row.to_vec(),
+            })
+            .collect();
+
+        self.validate()?;
+
+        Ok(self)
+    }
+
+    /// The values of the guided embed modal for the first embed and the link buttons, in the order
+    /// taken by [`MessageDraft::with_form`].
+    pub fn form(&self) -> [String; 5] {
+        let embed = self.embeds.get(0).cloned().unwrap_or_default();
+
+        let fields = embed
+            .fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{} | {}{}",
+                    field.name,
+                    field.value.replace('\n', " "),
+                    if field.inline { " | inline" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let buttons = self
+            .components
+            .iter()
+            .flat_map(|row| &row.components)
+            .map(|button| {
+                format!(
+                    "{} | {}",
+                    button.label.as_deref().unwrap_or(""),
+                    button.url.as_deref().unwrap_or("")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut style = Vec::new();
+        if let Some(colour) = embed.color {
+            style.push(format!("colour: #{:06x}", colour));
+        }
+        if let Some(url) = &embed.url {
+            style.push(format!("url: {}", url));
+        }
+        if let Some(author) = &embed.author {
+            style.push(format!("author: {}", author.name));
+        }
+        if let Some(footer) = &embed.footer {
+            style.push(format!("footer: {}", footer.text));
+        }
+        if let Some(image) = &embed.image {
+            style.push(format!("image: {}", image.url));
+        }
+        if let Some(thumbnail) = &embed.thumbnail {
+            style.push(format!("thumbnail: {}", thumbnail.url));
+        }
+
+        [
+            embed.title.unwrap_or_default(),
+            embed.description.unwrap_or_default(),
+            fields,
+            buttons,
+            style.join("\n"),
+        ]
+    }
+
+    /// Reads back the authorable parts of a message already sent by the bot.
+    pub fn from_message(message: &Message) -> Self {
+        // Embeds are converted through their JSON, which is the same shape as the draft's. Link
+        // previews are generated by Discord from the content, so aren't part of the draft
+        let embeds = message
+            .embeds
+            .iter()
+            .filter(|embed| embed.kind.as_deref() == Some("rich"))
+            .filter_map(|embed| {
+                serde_json::to_value(embed)
+                    .and_then(serde_json::from_value)
+                    .ok()
+            })
+            .collect();
+
+        let components = message
+            .components
+            .iter()
+            .map(|row| ActionRowDraft {
+                kind: COMPONENT_ACTION_ROW,
+                components: row
+                    .components
+                    .iter()
+                    .filter_map(|component| match component {
+                        ActionRowComponent::Button(button) if button.url.is_some() => {
+                            Some(ButtonDraft {
+                                kind: COMPONENT_BUTTON,
+                                style: BUTTON_STYLE_LINK,
+                                label: button.label.clone(),
+                                url: button.url.clone(),
+                            })
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            })
+            .filter(|row| !row.components.is_empty())
+            .collect();
+
+        Self {
+            content: non_empty(&message.content),
+            embeds,
+            components,
+        }
+    }
+
+    /// Checks the draft against Discord's limits, describing the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        let content = self.content.as_deref().unwrap_or("");
+
+        if content.trim().is_empty() && self.embeds.is_empty() {
+            return Err("The message needs some content or an embed!".to_string());
+        }
+        check_length("Content", content, MAX_CONTENT_LENGTH)?;
+
+        if self.embeds.len() > MAX_EMBEDS {
+            return Err(format!("A message can have at most {} embeds!", MAX_EMBEDS));
+        }
+
+        let mut total = 0;
+        for (i, embed) in self.embeds.iter().enumerate() {
+            let at = format!("Embed {}", i + 1);
+
+            let title = embed.title.as_deref().unwrap_or("");
+            let description = embed.description.as_deref().unwrap_or("");
+            check_length(&format!("{} title", at), title, MAX_TITLE_LENGTH)?;
+            check_length(
+                &format!("{} description", at),
+                description,
+                MAX_DESCRIPTION_LENGTH,
+            )?;
+            total += title.chars().count() + description.chars().count();
+
+            if title.is_empty()
+                && description.is_empty()
+                && embed.fields.is_empty()
+                && embed.image.is_none()
+                && embed.thumbnail.is_none()
+            {
+                return Err(format!(
+                    "{} needs a title, description, field or image!",
+                    at
+                ));
+            }
+
+            if embed.fields.len() > MAX_FIELDS {
+                return Err(format!("{} can have at most {} fields!", at, MAX_FIELDS));
+            }
+            for (j, field) in embed.fields.iter().enumerate() {
+                let at = format!("{} field {}", at, j + 1);
+                if field.name.trim().is_empty() || field.value.trim().is_empty() {
+                    return Err(format!("{} needs both a name and a value!", at));
+                }
+                check_length(&format!("{} name", at), &field.name, MAX_FIELD_NAME_LENGTH)?;
+                check_length(
+                    &format!("{} value", at),
+                    &field.value,
+                    MAX_FIELD_VALUE_LENGTH,
+                )?;
+                total += field.name.chars().count() + field.value.chars().count();
+            }
+
+            if let Some(author) = &embed.author {
+                check_length(&format!("{} author", at), &author.name, MAX_AUTHOR_LENGTH)?;
+                total += author.name.chars().count();
+                check_url(&format!("{} author url", at), author.url.as_deref())?;
+                check_url(&format!("{} author icon", at), author.icon_url.as_deref())?;
+            }
+            if let Some(footer) = &embed.footer {
+                check_length(&format!("{} footer", at), &footer.text, MAX_FOOTER_LENGTH)?;
+                total += footer.text.chars().count();
+                check_url(&format!("{} footer icon", at), footer.icon_url.as_deref())?;
+            }
+
+            if matches!(embed.color, Some(colour) if colour > 0xFFFFFF) {
+                return Err(format!("{} colour must be at most #FFFFFF!", at));
+            }
+            check_url(&format!("{} url", at), embed.url.as_deref())?;
+            check_url(
+                &format!("{} image", at),
+                embed.image.as_ref().map(|i| i.url.as_str()),
+            )?;
+            check_url(
+                &format!("{} thumbnail", at),
+                embed.thumbnail.as_ref().map(|i| i.url.as_str()),
+            )?;
+        }
+
+        if total > MAX_EMBEDS_TOTAL_LENGTH {
+            return Err(format!(
+                "Embeds have {} characters in total, the most allowed is {}!",
+                total, MAX_EMBEDS_TOTAL_LENGTH
+            ));
+        }
+
+        if self.components.len() > MAX_ACTION_ROWS {
+            return Err(format!(
+                "A message can have at most {} rows of buttons!",
+                MAX_ACTION_ROWS
+            ));
+        }
+        for (i, row) in self.components.iter().enumerate() {
+            if row.kind != COMPONENT_ACTION_ROW {
+                return Err(format!("Component {} must be an action row!", i + 1));
+            }
+            if row.components.is_empty() || row.components.len() > MAX_BUTTONS_PER_ROW {
+                return Err(format!(
+                    "Row {} must have between 1 and {} buttons!",
+                    i + 1,
+                    MAX_BUTTONS_PER_ROW
+                ));
+            }
+            for (j, button) in row.components.iter().enumerate() {
+                let at = format!("Row {} button {}", i + 1, j + 1);
+                if button.kind != COMPONENT_BUTTON || button.style != BUTTON_STYLE_LINK {
+                    return Err(format!("{} must be a link button!", at));
+                }
+                match &button.label {
+                    Some(label) if !label.trim().is_empty() => {
+                        check_length(&format!("{} label", at), label, MAX_BUTTON_LABEL_LENGTH)?
+                    }
+                    _ => return Err(format!("{} needs a label!", at)),
+                }
+                match &button.url {
+                    Some(url) => check_url(&format!("{} url", at), Some(url))?,
+                    None => return Err(format!("{} needs a url!", at)),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The embeds to send, which can be passed to `set_embeds` on a message builder.
+    pub fn create_embeds(&self) -> Vec<CreateEmbed> {
+        self.embeds
+            .iter()
+            .map(|draft| {
+                let mut embed = CreateEmbed::default();
+
+                if let Some(title) = &draft.title {
+                    embed.title(title);
+                }
+                if let Some(description) = &draft.description {
+                    embed.description(description);
+                }
+                if let Some(url) = &draft.url {
+                    embed.url(url);
+                }
+                if let Some(colour) = draft.color {
+                    embed.colour(colour);
+                }
+                if let Some(author) = &draft.author {
+                    embed.author(|a| {
+                        a.name(&author.name);
+                        if let Some(url) = &author.url {
+                            a.url(url);
+                        }
+                        if let Some(icon_url) = &author.icon_url {
+                            a.icon_url(icon_url);
+                        }
+                        a
+                    });
+                }
+                for field in &draft.fields {
+                    embed.field(&field.name, &field.value, field.inline);
+                }
+                if let Some(footer) = &draft.footer {
+                    embed.footer(|f| {
+                        f.text(&footer.text);
+                        if let Some(icon_url) = &footer.icon_url {
+                            f.icon_url(icon_url);
+                        }
+                        f
+                    });
+                }
+                if let Some(image) = &draft.image {
+                    embed.image(&image.url);
+                }
+                if let Some(thumbnail) = &draft.thumbnail {
+                    embed.thumbnail(&thumbnail.url);
+                }
+                if let Some(timestamp) = &draft.timestamp {
+                    // Set directly as the builder's timestamp type differs between versions
+                    embed
+                        .0
+                        .insert("timestamp", Value::String(timestamp.clone()));
+                }
+
+                embed
+            })
+            .collect()
+    }
+
+    /// The link buttons to send, which can be passed to `set_components` on a message builder.
+    pub fn create_components(&self) -> CreateComponents {
+        let mut components = CreateComponents::default();
+
+        for row in &self.components {
+            components.create_action_row(|action_row| {
+                for button in &row.components {
+                    action_row.create_button(|b| {
+                        b.style(ButtonStyle::Link)
+                            .label(button.label.as_deref().unwrap_or(""))
+                            .url(button.url.as_deref().unwrap_or(""))
+                    });
+                }
+                action_row
+            });
+        }
+
+        components
+    }
+}
+
+/// The non-empty lines of a modal input, numbered from 1.
+fn lines(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    if s.trim().is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+/// Parses a colour written as hex, e.g. `#5865F2`.
+fn parse_colour(s: &str) -> Result<u32, String> {
+    u32::from_str_radix(s.trim_start_matches('#'), 16)
+        .ok()
+        .filter(|colour| *colour <= 0xFFFFFF)
+        .ok_or_else(|| format!("Invalid colour `{}`, expected hex like `#5865F2`!", s))
+}
+
+fn check_length(name: &str, s: &str, max: usize) -> Result<(), String> {
+    let length = s.chars().count();
+    if length > max {
+        Err(format!(
+            "{} is {} characters long, the most allowed is {}!",
+            name, length, max
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_url(name: &str, url: Option<&str>) -> Result<(), String> {
+    match url {
+        Some(url) if !url.starts_with("https://") && !url.starts_with("http://") => {
+            Err(format!("{} must start with `https://` or `http://`!", name))
+        }
+        _ => Ok(()),
+    }
+}
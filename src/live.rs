@@ -0,0 +1,402 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use serenity::{
+    client::Context,
+    http::{Http, HttpError},
+    model::{
+        id::{ChannelId, MessageId, UserId},
+        interactions::{
+            application_command::ApplicationCommandInteraction, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{BotMessage, Challenge, Score, Setting, Solve},
+        Database,
+    },
+    drafts::{
+        EmbedDraft, FieldDraft, FooterDraft, MessageDraft, MAX_EMBEDS_TOTAL_LENGTH,
+        MAX_FIELD_VALUE_LENGTH,
+    },
+    get_option,
+    messages::{delivery_followup, new_message_name, record_revision, send_draft, MENTIONS_NONE},
+    response_flags, schedules,
+    selfroles::RoleMenu,
+    truncate, InteractionError, InteractionResult,
+};
+
+/// How often live messages are re-rendered. They are only edited if what they show has changed,
+/// so this also limits how often each one is edited when the data changes quickly.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How many players the live scoreboard shows.
+const SCOREBOARD_LENGTH: usize = 10;
+
+/// Sends a new message showing live data, which is kept up to date until it is frozen.
+pub async fn cmd_botmsg_live(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let kind = get_option(options, "kind")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let channel_id = get_option(options, "channel")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(ChannelId)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let until = get_option(options, "until")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str());
+
+    let title = get_option(options, "title")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .unwrap_or("Countdown");
+
+    let argument = match (kind, until) {
+        (BotMessage::LIVE_COUNTDOWN, Some(until)) => {
+            match schedules::parse_time(until.trim(), schedules::now()) {
+                Some(time) => Ok(Some(format!("{}:{}", time, title))),
+                None => Err(format!("Invalid time `{}`!", until)),
+            }
+        }
+        (BotMessage::LIVE_COUNTDOWN, None) => {
+            Err("Countdowns need a time to count to!".to_string())
+        }
+        _ => Ok(None),
+    };
+
+    let argument = match argument {
+        Ok(argument) => argument,
+        Err(why) => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| data.flags(flags).content(why))
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let name = match new_message_name(&ctx, db, &command).await? {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags))
+        })
+        .await?;
+
+    let draft = render(db, kind, argument.as_deref())?;
+    let result = send_draft(&ctx.http, db, channel_id, None, &draft, None, MENTIONS_NONE).await;
+
+    if let Ok(msg) = &result {
+        record_revision(db, msg, name.as_deref(), command.user.id.0)?;
+
+        if let Some(registered) = BotMessage::get_by_message_id(db, msg.id.0)? {
+            BotMessage::set_live(db, registered.id, Some(kind), argument.as_deref())?;
+            BotMessage::set_live_json(db, registered.id, &draft.to_json())?;
+        }
+    }
+
+    command
+        .create_followup_message(&ctx.http, |followup| {
+            delivery_followup(followup, None, result)
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Stops a live message from being updated, leaving it showing what it last did.
+pub async fn cmd_botmsg_freeze(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let message = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "message"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let content = match BotMessage::get_by_name(db, message.trim())? {
+        Some(registered) if registered.live.is_some() => {
+            BotMessage::set_live(db, registered.id, None, None)?;
+            format!("`{}` will no longer be updated.", registered.name)
+        }
+        Some(registered) => format!("`{}` isn't a live message!", registered.name),
+        None => format!("No bot-authored message named `{}`!", message),
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags).content(content))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Re-renders live messages and edits those whose data has changed, forever.
+pub async fn update_live_messages(http: Arc<Http>, db: Arc<Database>) {
+    let mut interval = tokio::time::interval(UPDATE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let messages = match BotMessage::get_live(&db) {
+            Ok(messages) => messages,
+            Err(why) => {
+                println!("Error when fetching live messages: {:?}", why);
+                continue;
+            }
+        };
+
+        for message in messages {
+            let kind = message.live.as_deref().unwrap_or("");
+            let draft = match render(&db, kind, message.live_argument.as_deref()) {
+                Ok(draft) => draft,
+                Err(why) => {
+                    println!(
+                        "Error when rendering live message {}: {:?}",
+                        message.name, why
+                    );
+                    continue;
+                }
+            };
+
+            let json = draft.to_json();
+            if message.live_json.as_deref() == Some(json.as_str()) {
+                continue;
+            }
+
+            if let Err(why) = draft.validate() {
+                println!(
+                    "Error when rendering live message {}: {}",
+                    message.name, why
+                );
+                continue;
+            }
+
+            let role_menu = match RoleMenu::for_message(&db, &message) {
+                Ok(role_menu) => role_menu,
+                Err(why) => {
+                    println!(
+                        "Error when rendering live message {}: {:?}",
+                        message.name, why
+                    );
+                    continue;
+                }
+            };
+
+            let result = match send_draft(
+                &http,
+                &db,
+                ChannelId(message.channel_id),
+                Some(MessageId(message.message_id)),
+                &draft,
+                role_menu.as_ref(),
+                MENTIONS_NONE,
+            )
+            .await
+            {
+                Ok(_) => BotMessage::set_live_json(&db, message.id, &json),
+                // A deleted message can't be updated any more, so it is frozen rather than retried
+                // every tick
+                Err(serenity::Error::Http(why))
+                    if matches!(
+                        why.as_ref(),
+                        HttpError::UnsuccessfulRequest(response)
+                            if response.status_code.as_u16() == 404
+                    ) =>
+                {
+                    println!(
+                        "Live message {} no longer exists, freezing it",
+                        message.name
+                    );
+                    BotMessage::set_live(&db, message.id, None, None).map(|_| ())
+                }
+                // Anything else is retried next tick, as the JSON isn't stored
+                Err(why) => {
+                    println!(
+                        "Error when updating live message {}: {:?}",
+                        message.name, why
+                    );
+                    continue;
+                }
+            };
+
+            if let Err(why) = result {
+                println!(
+                    "Error when updating live message {}: {:?}",
+                    message.name, why
+                );
+            }
+        }
+    }
+}
+
+/// Renders the current state of a kind of live data.
+fn render(
+    db: &Database,
+    kind: &str,
+    argument: Option<&str>,
+) -> Result<MessageDraft, InteractionError> {
+    let embed = match kind {
+        BotMessage::LIVE_SCOREBOARD => {
+            let scores = Score::get_scoreboard(db)?;
+
+            let description = if scores.is_empty() {
+                "No scores yet!".to_string()
+            } else {
+                scores
+                    .iter()
+                    .take(SCOREBOARD_LENGTH)
+                    .enumerate()
+                    .map(|(i, score)| {
+                        format!(
+                            "`#{}` {} **{}** points",
+                            i + 1,
+                            UserId(score.user_id).mention(),
+                            score.points
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            EmbedDraft {
+                title: Some("🏆 Scoreboard".to_string()),
+                description: Some(description),
+                color: Some(0xFFD700),
+                footer: Some(FooterDraft {
+                    text: format!(
+                        "Top {} of {} players",
+                        scores.len().min(SCOREBOARD_LENGTH),
+                        scores.len()
+                    ),
+                    icon_url: None,
+                }),
+                ..Default::default()
+            }
+        }
+        BotMessage::LIVE_CHALLENGES => {
+            let challenges = Challenge::get_all(db)?;
+            let counts = Solve::get_counts(db)?;
+
+            let mut by_category: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+            for challenge in &challenges {
+                let solves = counts.get(&challenge.id).copied().unwrap_or(0);
+                by_category
+                    .entry(&challenge.category)
+                    .or_default()
+                    .push(format!(
+                        "**{}** ({} points): {} solve{}",
+                        challenge.name,
+                        challenge.points,
+                        solves,
+                        if solves == 1 { "" } else { "s" }
+                    ));
+            }
+
+            // Discord allows at most 25 fields, which share what's left of the limit on the length
+            // of the whole embed after the title and their names
+            let title = "🚩 Challenges";
+            let categories = by_category.into_iter().take(25).collect::<Vec<_>>();
+            let value_length = MAX_EMBEDS_TOTAL_LENGTH
+                .saturating_sub(title.chars().count())
+                .saturating_sub(categories.iter().map(|(c, _)| c.chars().count()).sum())
+                / categories.len().max(1);
+
+            EmbedDraft {
+                title: Some(title.to_string()),
+                description: if challenges.is_empty() {
+                    Some("No challenges yet!".to_string())
+                } else {
+                    None
+                },
+                color: Some(0x0080FF),
+                fields: categories
+                    .into_iter()
+                    .map(|(category, lines)| FieldDraft {
+                        name: category.to_string(),
+                        value: truncate(
+                            &lines.join("\n"),
+                            value_length.clamp(1, MAX_FIELD_VALUE_LENGTH),
+                        ),
+                        inline: false,
+                    })
+                    .collect(),
+                ..Default::default()
+            }
+        }
+        BotMessage::LIVE_COUNTDOWN => {
+            let (time, title) = argument
+                .and_then(|argument| argument.split_once(':'))
+                .and_then(|(time, title)| Some((time.parse::<i64>().ok()?, title)))
+                .ok_or(InteractionError::UnprocessableRequest)?;
+
+            // Discord shows the relative time ticking down by itself, this only changes once
+            // the time has come
+            let description = if schedules::now() < time {
+                format!("Counting down to <t:{}:F>, <t:{}:R>.", time, time)
+            } else {
+                format!("Time's up! That was <t:{}:F>, <t:{}:R>.", time, time)
+            };
+
+            EmbedDraft {
+                title: Some(format!("⏳ {}", title)),
+                description: Some(description),
+                color: Some(0xFF8000),
+                ..Default::default()
+            }
+        }
+        kind => {
+            return Err(InteractionError::Other(format!(
+                "Invalid live message kind: '{}'",
+                kind
+            )))
+        }
+    };
+
+    Ok(MessageDraft {
+        content: None,
+        embeds: vec![embed],
+        components: Vec::new(),
+    })
+}
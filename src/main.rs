@@ -1,27 +1,52 @@
-use std::{collections::HashMap, env};
+use std::{
+    collections::HashMap,
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use serenity::{
     async_trait,
     model::{
         channel::ChannelType,
         gateway::Ready,
-        id::GuildId,
+        id::{GuildId, UserId},
         interactions::{
             application_command::{
-                ApplicationCommandInteraction, ApplicationCommandOptionType,
-                ApplicationCommandPermissionType,
+                ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
+                ApplicationCommandOptionType, ApplicationCommandPermissionType,
+                ApplicationCommandType,
             },
-            Interaction, InteractionResponseType,
+            message_component::{ActionRow, ActionRowComponent},
+            Interaction, InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
         },
     },
     prelude::*,
 };
 
 mod db;
-use db::Database;
+use db::{models::Setting, Database};
 
+mod announcements;
+mod browser;
 mod challenges;
+mod config;
+mod decoys;
+mod discussions;
+mod drafts;
+mod live;
 mod messages;
+mod previews;
+mod profile;
+mod rewards;
+mod sanctions;
+mod schedules;
+mod scoreboard;
+mod scoring;
+mod selfroles;
+mod tickets;
 
 #[derive(Debug)]
 pub enum InteractionError {
@@ -62,9 +87,80 @@ impl From<serenity::Error> for InteractionError {
 
 pub type InteractionResult = Result<(), InteractionError>;
 
+/// Finds an option by name among a command's (or subcommand's) options.
+///
+/// Optional options are omitted entirely by Discord when not supplied, so they can't be looked up
+/// by position.
+pub fn get_option<'a>(
+    options: &'a [ApplicationCommandInteractionDataOption],
+    name: &str,
+) -> Option<&'a ApplicationCommandInteractionDataOption> {
+    options.iter().find(|o| o.name == name)
+}
+
+/// Gets the id of the user chosen for a user option.
+pub fn get_user_option(
+    options: &[ApplicationCommandInteractionDataOption],
+    name: &str,
+) -> Option<UserId> {
+    get_option(options, name)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse().ok())
+        .map(UserId)
+}
+
+/// Gets the value of the input text in the `index`th action row of a modal submission.
+pub fn get_input_value(rows: &[ActionRow], index: usize) -> Option<&str> {
+    rows.get(index)
+        .and_then(|r| r.components.get(0))
+        .and_then(|c| match c {
+            ActionRowComponent::InputText(input_text) => Some(input_text.value.as_str()),
+            _ => None,
+        })
+}
+
+/// Shortens `s` to at most `max_chars` characters, marking it with an ellipsis if it was cut.
+///
+/// Useful for fitting user-provided names into Discord's length limits, e.g. of modal titles.
+pub fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        s.to_string()
+    } else {
+        let mut truncated: String = s.chars().take(max_chars.saturating_sub(1)).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+/// Splits a component's custom id of the form `<id>:<argument>`, used to carry state such as the
+/// challenge a modal was opened for.
+pub fn split_custom_id(custom_id: &str) -> (&str, Option<&str>) {
+    match custom_id.split_once(':') {
+        Some((id, argument)) => (id, Some(argument)),
+        None => (custom_id, None),
+    }
+}
+
+/// Gets the flags for a response with the given outcome (e.g. [`Setting::OUTCOME_ADMIN`]), making
+/// it ephemeral unless that outcome has been made public with `/config publicresponses`.
+pub fn response_flags(
+    db: &Database,
+    outcome: &str,
+) -> db::Result<InteractionApplicationCommandCallbackDataFlags> {
+    Ok(if Setting::is_public(db, outcome)? {
+        InteractionApplicationCommandCallbackDataFlags::empty()
+    } else {
+        InteractionApplicationCommandCallbackDataFlags::EPHEMERAL
+    })
+}
+
 struct Handler {
-    db: Database,
+    db: Arc<Database>,
     admin_role_id: u64,
+    /// Set once the background tasks (delivering scheduled messages and updating live ones) are
+    /// running, since `ready` is called again whenever the bot reconnects.
+    tasks_started: AtomicBool,
 }
 
 #[async_trait]
@@ -73,12 +169,49 @@ impl EventHandler for Handler {
         match interaction {
             Interaction::ApplicationCommand(command) => {
                 if let Err(why) = match command.data.name.as_str() {
-                    "submitflag" => challenges::cmd_submitflag(ctx, command).await,
+                    "submitflag" => challenges::cmd_submitflag(ctx, &self.db, command).await,
                     "ping" => cmd_ping(ctx, command).await,
+                    "scoreboard" => scoreboard::cmd_scoreboard(ctx, &self.db, command).await,
+                    "challenges" => browser::cmd_challenges(ctx, &self.db, command).await,
+                    "profile" | profile::CMD_VIEW_PROFILE => {
+                        profile::cmd_profile(ctx, &self.db, command).await
+                    }
                     "addchallenge" => {
                         challenges::cmd_addchallenge(ctx, command, self.admin_role_id).await
                     }
-                    "botmsg" => messages::cmd_botmsg(ctx, command, self.admin_role_id).await,
+                    "botmsg" => {
+                        messages::cmd_botmsg(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "decoy" => decoys::cmd_decoy(ctx, &self.db, command, self.admin_role_id).await,
+                    "config" => {
+                        config::cmd_config(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "wronganswer" => {
+                        challenges::cmd_wronganswer(ctx, &self.db, command, self.admin_role_id)
+                            .await
+                    }
+                    "addstage" => {
+                        challenges::cmd_addstage(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "challenge" => {
+                        challenges::cmd_challenge(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "score" => scoring::cmd_score(ctx, &self.db, command, self.admin_role_id).await,
+                    "sanction" => {
+                        sanctions::cmd_sanction(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "rolereward" => {
+                        rewards::cmd_rolereward(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "selfrole" => {
+                        selfroles::cmd_selfrole(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "ticket" => {
+                        tickets::cmd_ticket(ctx, &self.db, command, self.admin_role_id).await
+                    }
+                    "tickets" => {
+                        tickets::cmd_tickets(ctx, &self.db, command, self.admin_role_id).await
+                    }
                     command_name => Err(InteractionError::Other(format!(
                         "Invalid command invoked: '{}'",
                         command_name
@@ -88,11 +221,19 @@ impl EventHandler for Handler {
                 }
             }
             Interaction::ModalSubmit(interaction) => {
-                if let Err(why) = match interaction.data.custom_id.as_str() {
-                    challenges::ID_MODAL_FLAG_SUBMIT => {
-                        challenges::modal_submit_flag_response(ctx, &self.db, interaction).await
+                let custom_id = interaction.data.custom_id.clone();
+
+                if let Err(why) = match split_custom_id(&custom_id) {
+                    (challenges::ID_MODAL_FLAG_SUBMIT, challenge_id) => {
+                        challenges::modal_submit_flag_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            challenge_id,
+                        )
+                        .await
                     }
-                    challenges::ID_MODAL_CHAL_ADD => {
+                    (challenges::ID_MODAL_CHAL_ADD, None) => {
                         challenges::modal_chal_add_response(
                             ctx,
                             &self.db,
@@ -101,22 +242,223 @@ impl EventHandler for Handler {
                         )
                         .await
                     }
-                    messages::ID_MODAL_BOTMSG_SEND => {
-                        messages::modal_botmsg_send_response(ctx, interaction, self.admin_role_id)
-                            .await
+                    (challenges::ID_MODAL_CHAL_EDIT, Some(challenge_id)) => {
+                        challenges::modal_chal_edit_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            challenge_id,
+                            self.admin_role_id,
+                        )
+                        .await
                     }
-                    messages::ID_MODAL_BOTMSG_EDIT => {
-                        messages::modal_botmsg_edit_response(ctx, interaction, self.admin_role_id)
-                            .await
+                    (challenges::ID_MODAL_FLAG_ROTATE, Some(argument)) => {
+                        challenges::modal_flag_rotate_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            argument,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (challenges::ID_MODAL_STAGE_ADD, Some(challenge_id)) => {
+                        challenges::modal_stage_add_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            challenge_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (messages::ID_MODAL_BOTMSG_SEND, name) => {
+                        messages::modal_botmsg_send_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            name,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (messages::ID_MODAL_BOTMSG_EMBED, Some(target)) => {
+                        messages::modal_botmsg_embed_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            target,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (messages::ID_MODAL_BOTMSG_JSON, Some(target)) => {
+                        messages::modal_botmsg_json_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            target,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (schedules::ID_MODAL_BOTMSG_SCHEDULE, scheduled_id) => {
+                        schedules::modal_botmsg_schedule_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            scheduled_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (messages::ID_MODAL_BOTMSG_EDIT, None) => {
+                        messages::modal_botmsg_edit_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            self.admin_role_id,
+                        )
+                        .await
                     }
-                    modal_id => Err(InteractionError::Other(format!(
+                    _ => Err(InteractionError::Other(format!(
                         "Invalid id in modal submission: {:?}",
-                        modal_id
+                        custom_id
                     ))),
                 } {
                     println!("Error when responding to modal submission: {:?}", why);
                 }
             }
+            Interaction::MessageComponent(interaction) => {
+                let custom_id = interaction.data.custom_id.clone();
+
+                if let Err(why) = match split_custom_id(&custom_id) {
+                    (scoreboard::ID_BUTTON_SCOREBOARD_PAGE, Some(page)) => {
+                        scoreboard::button_scoreboard_page_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            page,
+                        )
+                        .await
+                    }
+                    (browser::ID_SELECT_CHALLENGE, None) => {
+                        browser::select_challenge_response(ctx, &self.db, interaction).await
+                    }
+                    (browser::ID_BUTTON_CHALLENGES_PAGE, Some(page)) => {
+                        browser::button_challenges_page_response(ctx, &self.db, interaction, page)
+                            .await
+                    }
+                    (browser::ID_BUTTON_FLAG_SUBMIT, Some(challenge_id)) => {
+                        browser::button_flag_submit_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            challenge_id,
+                        )
+                        .await
+                    }
+                    (tickets::ID_BUTTON_TICKET_CLAIM, Some(ticket_id)) => {
+                        tickets::button_ticket_claim_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            ticket_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (tickets::ID_BUTTON_TICKET_CLOSE, Some(ticket_id)) => {
+                        tickets::button_ticket_close_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            ticket_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (selfroles::ID_BUTTON_SELF_ROLE, Some(role_id)) => {
+                        selfroles::button_self_role_response(ctx, &self.db, interaction, role_id)
+                            .await
+                    }
+                    (selfroles::ID_SELECT_SELF_ROLE, Some(group)) => {
+                        selfroles::select_self_role_response(ctx, &self.db, interaction, group)
+                            .await
+                    }
+                    (previews::ID_BUTTON_BOTMSG_CONFIRM, Some(pending_id)) => {
+                        previews::button_botmsg_confirm_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            pending_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (previews::ID_BUTTON_BOTMSG_REVISE, Some(pending_id)) => {
+                        previews::button_botmsg_revise_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            pending_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (previews::ID_BUTTON_BOTMSG_PING, Some(pending_id)) => {
+                        previews::button_botmsg_ping_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            pending_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (previews::ID_SELECT_BOTMSG_MENTIONS, Some(pending_id)) => {
+                        previews::select_botmsg_mentions_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            pending_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    (previews::ID_BUTTON_BOTMSG_CANCEL, Some(pending_id)) => {
+                        previews::button_botmsg_cancel_response(
+                            ctx,
+                            &self.db,
+                            interaction,
+                            pending_id,
+                            self.admin_role_id,
+                        )
+                        .await
+                    }
+                    _ => Err(InteractionError::Other(format!(
+                        "Invalid id in message component interaction: {:?}",
+                        custom_id
+                    ))),
+                } {
+                    println!("Error when responding to message component: {:?}", why);
+                }
+            }
+            Interaction::Autocomplete(autocomplete) => {
+                if let Err(why) = match autocomplete.data.name.as_str() {
+                    "submitflag" | "wronganswer" | "addstage" | "challenge" | "score"
+                    | "rolereward" | "ticket" => {
+                        challenges::autocomplete_challenge(ctx, &self.db, autocomplete).await
+                    }
+                    "botmsg" => messages::autocomplete_botmsg(ctx, &self.db, autocomplete).await,
+                    command_name => Err(InteractionError::Other(format!(
+                        "Invalid command in autocomplete: '{}'",
+                        command_name
+                    ))),
+                } {
+                    println!("Error when responding to autocomplete: {:?}", why);
+                }
+            }
             _ => {}
         }
     }
@@ -124,6 +466,17 @@ impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
 
+        if !self.tasks_started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(schedules::deliver_scheduled_messages(
+                ctx.http.clone(),
+                self.db.clone(),
+            ));
+            tokio::spawn(live::update_live_messages(
+                ctx.http.clone(),
+                self.db.clone(),
+            ));
+        }
+
         let guild_id = GuildId(
             env::var("GUILD_ID")
                 .expect("Expected GUILD_ID in environment")
@@ -140,6 +493,74 @@ impl EventHandler for Handler {
                     command
                         .name("submitflag")
                         .description("Invoke this command to submit a flag!")
+                        .create_option(|option| {
+                            option
+                                .name("challenge")
+                                .description("Only check the flag against this challenge")
+                                .required(false)
+                                .kind(ApplicationCommandOptionType::Integer)
+                                .set_autocomplete(true)
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("scoreboard")
+                        .description("Show the current standings")
+                        .create_option(|option| {
+                            option
+                                .name("me")
+                                .description("Jump to your own position")
+                                .required(false)
+                                .kind(ApplicationCommandOptionType::Boolean)
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("challenges")
+                        .description("Browse the challenges")
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("profile")
+                        .description("Show a player's CTF profile")
+                        .create_option(|option| {
+                            option
+                                .name("user")
+                                .description("The player, yourself if omitted")
+                                .required(false)
+                                .kind(ApplicationCommandOptionType::User)
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name(profile::CMD_VIEW_PROFILE)
+                        .kind(ApplicationCommandType::User)
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("ticket")
+                        .description("Ask the admins for help, e.g. if a challenge is broken")
+                        .create_option(|option| {
+                            option
+                                .name("description")
+                                .description("What do you need help with?")
+                                .required(true)
+                                .kind(ApplicationCommandOptionType::String)
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("challenge")
+                                .description("The challenge this is about, if any")
+                                .required(false)
+                                .kind(ApplicationCommandOptionType::Integer)
+                                .set_autocomplete(true)
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("tickets")
+                        .description("ROOT ONLY: List open tickets")
+                        .default_permission(false)
                 })
                 .create_application_command(|command| {
                     command
@@ -149,59 +570,1026 @@ impl EventHandler for Handler {
                 })
                 .create_application_command(|command| {
                     command
-                        .name("botmsg")
-                        .description("ROOT ONLY: Send and edit bot-authored messages")
+                        .name("addstage")
+                        .description("ROOT ONLY: add a stage with its own flag to a challenge")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .name("challenge")
+                                .description("The challenge to add a stage to")
+                                .required(true)
+                                .kind(ApplicationCommandOptionType::Integer)
+                                .set_autocomplete(true)
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("challenge")
+                        .description("ROOT ONLY: Manage challenges")
                         .default_permission(false)
                         .create_option(|option| {
                             option
                                 .kind(ApplicationCommandOptionType::SubCommand)
-                                .name("send")
-                                .description("ROOT ONLY: Send a bot-authored message")
+                                .name("edit")
+                                .description("ROOT ONLY: Edit a challenge")
                                 .create_sub_option(|option| {
                                     option
-                                        .name("channel")
-                                        .description("The destination channel for the message")
+                                        .name("challenge")
+                                        .description("The challenge to edit")
                                         .required(true)
-                                        .kind(ApplicationCommandOptionType::Channel)
-                                        .channel_types(&[ChannelType::Text])
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
                                 })
                         })
                         .create_option(|option| {
                             option
                                 .kind(ApplicationCommandOptionType::SubCommand)
-                                .name("edit")
-                                .description("ROOT ONLY: Edit a bot-authored message")
+                                .name("delete")
+                                .description("ROOT ONLY: Archive a challenge, keeping its solves")
                                 .create_sub_option(|option| {
                                     option
-                                        .name("channel")
+                                        .name("challenge")
+                                        .description("The challenge to delete")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("rotate")
+                                .description("ROOT ONLY: Replace a leaked flag")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("challenge")
+                                        .description("The challenge whose flag leaked")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("stage")
+                                        .description("The stage whose flag leaked")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("grace")
                                         .description(
-                                            "The channel containing the bot-authored message",
+                                            "Minutes the old flag is still accepted for (default 10)",
                                         )
-                                        .required(true)
-                                        .kind(ApplicationCommandOptionType::Channel)
-                                        .channel_types(&[ChannelType::Text])
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
                                 })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("thread")
+                                .description("ROOT ONLY: Create a challenge's discussion thread")
                                 .create_sub_option(|option| {
                                     option
-                                        .name("msgid")
-                                        .description("The id of the bot-authored message to edit")
+                                        .name("challenge")
+                                        .description("The challenge")
                                         .required(true)
-                                        .kind(ApplicationCommandOptionType::String)
-                                        .channel_types(&[ChannelType::Text])
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
                                 })
                         })
                 })
-        })
-        .await
-        .unwrap();
-
-        let command_id_map: HashMap<String, u64> = commands
-            .iter()
+                .create_application_command(|command| {
+                    command
+                        .name("botmsg")
+                        .description("ROOT ONLY: Send and edit bot-authored messages")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("send")
+                                .description("ROOT ONLY: Send a bot-authored message")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, thread or forum to send to; forum posts are titled after the message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::SEND_CHANNEL_TYPES)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("name")
+                                        .description("A name to edit the new message by later")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("edit")
+                                .description("ROOT ONLY: Edit a bot-authored message")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name or id of the bot-authored message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel of the message, if it isn't registered")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::MESSAGE_CHANNEL_TYPES)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("embed")
+                                .description("ROOT ONLY: Author a bot-authored embed with link buttons")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, thread or forum to send a new message to")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::SEND_CHANNEL_TYPES)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name or id of a bot-authored message to edit instead")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("name")
+                                        .description("A name to edit the new message by later")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("json")
+                                .description("ROOT ONLY: Send or overwrite a bot-authored message from JSON")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, thread or forum to send a new message to")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::SEND_CHANNEL_TYPES)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name or id of a bot-authored message to edit instead")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("name")
+                                        .description("A name to edit the new message by later")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("import")
+                                .description("ROOT ONLY: Send or overwrite a bot-authored message from a Discohook JSON file")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("file")
+                                        .description("The Discord or Discohook message JSON file")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Attachment)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, thread or forum to send a new message to")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::SEND_CHANNEL_TYPES)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name or id of a bot-authored message to overwrite instead")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("name")
+                                        .description("A name to edit the new message by later")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("export")
+                                .description("ROOT ONLY: Dump a bot-authored message as Discohook JSON")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name or id of the bot-authored message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel of the message, if it isn't registered")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::MESSAGE_CHANNEL_TYPES)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("list")
+                                .description("ROOT ONLY: List registered bot-authored messages")
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("history")
+                                .description("ROOT ONLY: List past revisions of a bot-authored message")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name of the bot-authored message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("revert")
+                                .description("ROOT ONLY: Restore a bot-authored message to a past revision")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name of the bot-authored message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("revision")
+                                        .description("The revision to restore, from /botmsg history")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("live")
+                                .description("ROOT ONLY: Send a bot-authored message that keeps itself up to date")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("kind")
+                                        .description("What the message shows")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .add_string_choice("Top 10 scoreboard", "scoreboard")
+                                        .add_string_choice("Challenges and solve counts", "challenges")
+                                        .add_string_choice("Countdown", "countdown")
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, thread or forum to send to; forum posts are titled after the message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::SEND_CHANNEL_TYPES)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("until")
+                                        .description("Countdowns only: YYYY-MM-DD HH:MM in UTC, or e.g. +2h")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("title")
+                                        .description("Countdowns only: what is being counted down to")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("name")
+                                        .description("A name to refer to the new message by later")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("freeze")
+                                .description("ROOT ONLY: Stop a live bot-authored message from updating")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name of the live message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("roles")
+                                .description("ROOT ONLY: Offer a group of roles under a bot-authored message")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The name of the bot-authored message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("group")
+                                        .description("The group of roles to offer, leave out to offer none")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("style")
+                                        .description("How the roles are offered, buttons by default")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                        .add_string_choice("Buttons", "buttons")
+                                        .add_string_choice("Select menu", "select")
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("crosspost")
+                                .description("ROOT ONLY: Set whether bot-authored messages are published to followers")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The announcement channel")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(&[ChannelType::News])
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("enabled")
+                                        .description("Whether new messages sent there are published")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Boolean)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("schedule")
+                                .description(
+                                    "ROOT ONLY: Schedule a bot-authored message, once or repeating",
+                                )
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The destination channel for the message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(messages::SEND_CHANNEL_TYPES)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("schedules")
+                                .description("ROOT ONLY: List scheduled bot-authored messages")
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("reschedule")
+                                .description("ROOT ONLY: Edit a scheduled bot-authored message")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("id")
+                                        .description("The id of the scheduled message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("unschedule")
+                                .description("ROOT ONLY: Cancel a scheduled bot-authored message")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("id")
+                                        .description("The id of the scheduled message")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("decoy")
+                        .description("ROOT ONLY: Manage decoy flags that catch cheaters")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("add")
+                                .description("ROOT ONLY: Register a decoy flag")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("flag")
+                                        .description("The decoy flag")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("description")
+                                        .description("Where the decoy is planted, shown to admins")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("penalty")
+                                        .description("Points deducted from the submitter")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .min_int_value(0)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("list")
+                                .description("ROOT ONLY: List decoy flags")
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("remove")
+                                .description("ROOT ONLY: Remove a decoy flag")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("id")
+                                        .description("The id of the decoy flag")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("config")
+                        .description("ROOT ONLY: Configure the bot")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("adminchannel")
+                                .description("ROOT ONLY: Set the channel admin notifications go to")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, omit to disable notifications")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(&[ChannelType::Text])
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("solveschannel")
+                                .description("ROOT ONLY: Set the channel solves are announced in")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, omit to disable announcements")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(&[ChannelType::Text])
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("discussionchannel")
+                                .description(
+                                    "ROOT ONLY: Set the channel challenge discussion threads go in",
+                                )
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description("The channel, omit to disable threads")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(&[ChannelType::Text])
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("ticketchannel")
+                                .description("ROOT ONLY: Set the channel ticket threads go in")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("channel")
+                                        .description(
+                                            "The channel, omit to use the channel /ticket is run in",
+                                        )
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Channel)
+                                        .channel_types(&[ChannelType::Text])
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("bloodbonus")
+                                .description(
+                                    "ROOT ONLY: Set bonus points for the first three solves",
+                                )
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("first")
+                                        .description("Bonus points for the first solve")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("second")
+                                        .description("Bonus points for the second solve")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("third")
+                                        .description("Bonus points for the third solve")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("publicresponses")
+                                .description(
+                                    "ROOT ONLY: Choose which responses everyone can see",
+                                )
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("correct")
+                                        .description("Show correct flag submissions publicly")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Boolean)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("incorrect")
+                                        .description("Show incorrect flag submissions publicly")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Boolean)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("admin")
+                                        .description("Show responses to admin commands publicly")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Boolean)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("flagformat")
+                                .description(
+                                    "ROOT ONLY: Set the flag format submissions must match",
+                                )
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("prefix")
+                                        .description("The flag prefix, e.g. cheri for cheri{...}")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("wronganswer")
+                        .description("ROOT ONLY: Manage custom responses to known wrong answers")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("add")
+                                .description("ROOT ONLY: Add a known wrong answer")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("challenge")
+                                        .description("The challenge the answer relates to")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("answer")
+                                        .description("The wrong answer")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("message")
+                                        .description("The message shown when it is submitted")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("list")
+                                .description("ROOT ONLY: List known wrong answers for a challenge")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("challenge")
+                                        .description("The challenge")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("remove")
+                                .description("ROOT ONLY: Remove a known wrong answer")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("id")
+                                        .description("The id of the known wrong answer")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("score")
+                        .description("ROOT ONLY: Manage solves and scores")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("award")
+                                .description("ROOT ONLY: Award a user a solve for a challenge")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("The user to award the solve to")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::User)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("challenge")
+                                        .description("The challenge")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("revoke")
+                                .description("ROOT ONLY: Revoke a user's solve of a challenge")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("The user to revoke the solve from")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::User)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("challenge")
+                                        .description("The challenge")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("adjust")
+                                .description("ROOT ONLY: Give a user bonus or penalty points")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("The user whose score to adjust")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::User)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("points")
+                                        .description("Points to add, negative for a penalty")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("reason")
+                                        .description("Why the score is being adjusted")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("history")
+                                .description("ROOT ONLY: List a user's score adjustments")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("The user")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::User)
+                                })
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("sanction")
+                        .description("ROOT ONLY: Disqualify or shadow ban participants")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("disqualify")
+                                .description("ROOT ONLY: Hide a user from the scoreboard and reject their submissions")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("The user to disqualify")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::User)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("reason")
+                                        .description("Why the user is being disqualified")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("shadowban")
+                                .description("ROOT ONLY: Hide a user from the scoreboard and silently ignore their solves")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("The user to shadow ban")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::User)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("reason")
+                                        .description("Why the user is being shadow banned")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("lift")
+                                .description("ROOT ONLY: Lift a user's sanction")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("The user")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::User)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("list")
+                                .description("ROOT ONLY: List sanctioned users")
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("rolereward")
+                        .description("ROOT ONLY: Manage roles granted for solving challenges")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("add")
+                                .description("ROOT ONLY: Grant a role for a challenge, a whole category or a score")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("role")
+                                        .description("The role to grant")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Role)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("challenge")
+                                        .description("Grant the role for solving this challenge")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                        .set_autocomplete(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("category")
+                                        .description("Grant the role for solving every challenge in this category")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("score")
+                                        .description("Grant the role for reaching this many points")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("list")
+                                .description("ROOT ONLY: List role rewards")
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("remove")
+                                .description("ROOT ONLY: Remove a role reward")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("id")
+                                        .description("The id of the role reward")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Integer)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("reconcile")
+                                .description("ROOT ONLY: Re-sync reward roles of every member")
+                        })
+                })
+                .create_application_command(|command| {
+                    command
+                        .name("selfrole")
+                        .description("ROOT ONLY: Manage roles members can give themselves")
+                        .default_permission(false)
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("allow")
+                                .description("ROOT ONLY: Let members pick a role from a group")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("role")
+                                        .description("The role members may pick")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Role)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("group")
+                                        .description("The group of roles offered together")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("label")
+                                        .description("What the role's button says, the role's name by default")
+                                        .required(false)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("disallow")
+                                .description("ROOT ONLY: Stop members picking a role")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("role")
+                                        .description("The role members may no longer pick")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Role)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("exclusive")
+                                .description("ROOT ONLY: Set whether members may hold only one role of a group")
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("group")
+                                        .description("The group of roles")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("exclusive")
+                                        .description("Whether picking a role of the group removes the others")
+                                        .required(true)
+                                        .kind(ApplicationCommandOptionType::Boolean)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .kind(ApplicationCommandOptionType::SubCommand)
+                                .name("list")
+                                .description("ROOT ONLY: List the roles members can pick")
+                        })
+                })
+        })
+        .await
+        .unwrap();
+
+        let command_id_map: HashMap<String, u64> = commands
+            .iter()
             .map(|cmd| (cmd.name.clone(), cmd.id.0))
             .collect();
 
         let addchallenge_id = *command_id_map.get("addchallenge").unwrap();
+        let addstage_id = *command_id_map.get("addstage").unwrap();
+        let challenge_id = *command_id_map.get("challenge").unwrap();
         let botmsg_id = *command_id_map.get("botmsg").unwrap();
+        let decoy_id = *command_id_map.get("decoy").unwrap();
+        let config_id = *command_id_map.get("config").unwrap();
+        let wronganswer_id = *command_id_map.get("wronganswer").unwrap();
+        let score_id = *command_id_map.get("score").unwrap();
+        let sanction_id = *command_id_map.get("sanction").unwrap();
+        let rolereward_id = *command_id_map.get("rolereward").unwrap();
+        let selfrole_id = *command_id_map.get("selfrole").unwrap();
+        let tickets_id = *command_id_map.get("tickets").unwrap();
 
         let _perms =
             GuildId::set_application_commands_permissions(&guild_id, &ctx.http, |permissions| {
@@ -213,6 +1601,20 @@ impl EventHandler for Handler {
                                 .permission(true)
                         })
                     })
+                    .create_application_command(|command| {
+                        command.id(addstage_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(challenge_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
                     .create_application_command(|command| {
                         command.id(botmsg_id).create_permissions(|perm| {
                             perm.kind(ApplicationCommandPermissionType::Role)
@@ -220,6 +1622,62 @@ impl EventHandler for Handler {
                                 .permission(true)
                         })
                     })
+                    .create_application_command(|command| {
+                        command.id(decoy_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(config_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(wronganswer_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(score_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(sanction_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(rolereward_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(selfrole_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
+                    .create_application_command(|command| {
+                        command.id(tickets_id).create_permissions(|perm| {
+                            perm.kind(ApplicationCommandPermissionType::Role)
+                                .id(self.admin_role_id)
+                                .permission(true)
+                        })
+                    })
             })
             .await
             .unwrap();
@@ -256,8 +1714,9 @@ async fn main() {
 
     let mut client = Client::builder(token)
         .event_handler(Handler {
-            db: Database::new(&sqlite_db_path),
+            db: Arc::new(Database::new(&sqlite_db_path)),
             admin_role_id,
+            tasks_started: AtomicBool::new(false),
         })
         .application_id(application_id)
         .await
@@ -1,18 +1,78 @@
+use serde_json::Value;
 use serenity::{
+    builder::{
+        CreateAllowedMentions, CreateInteractionResponseData, CreateInteractionResponseFollowup,
+        ParseValue,
+    },
     client::Context,
+    http::Http,
     model::{
-        id::{ChannelId, RoleId},
+        channel::{ChannelType, Message},
+        id::{AttachmentId, ChannelId, MessageId, RoleId, UserId},
         interactions::{
             application_command::ApplicationCommandInteraction,
+            autocomplete::AutocompleteInteraction,
             message_component::{ActionRowComponent, ButtonStyle, InputTextStyle},
             modal::ModalSubmitInteraction,
-            InteractionResponseType,
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{
+            AuditLog, BotMessage, BotMessageRevision, CrosspostChannel, PendingDraft, Setting,
         },
+        Database,
     },
+    drafts::MessageDraft,
+    get_input_value, get_option, live, previews, response_flags, schedules,
+    selfroles::{self, RoleMenu},
+    truncate, InteractionError, InteractionResult,
 };
 
-use crate::{InteractionError, InteractionResult};
+/// The longest value Discord accepts in a modal's text input.
+pub const MAX_INPUT_LENGTH: usize = 4000;
+
+/// Bot-authored messages sent like this ping nobody.
+pub const MENTIONS_NONE: &str = "none";
+/// Bot-authored messages sent like this ping the users and roles they mention.
+pub const MENTIONS_ROLES: &str = "roles";
+/// Bot-authored messages sent like this ping everyone they mention, `@everyone` and `@here` too.
+pub const MENTIONS_EVERYONE: &str = "everyone";
+
+/// The biggest message JSON file accepted by `/botmsg import`, in bytes.
+const MAX_IMPORT_SIZE: u64 = 64 * 1024;
+
+/// The longest name a bot-authored message can be registered as.
+const MAX_NAME_LENGTH: usize = 32;
 
+/// How many revisions `/botmsg history` shows.
+const HISTORY_LENGTH: usize = 15;
+
+/// Channels new bot-authored messages can be sent to.
+pub const SEND_CHANNEL_TYPES: &[ChannelType] = &[
+    ChannelType::Text,
+    ChannelType::News,
+    ChannelType::NewsThread,
+    ChannelType::PublicThread,
+    ChannelType::PrivateThread,
+    ChannelType::Forum,
+];
+
+/// Channels bot-authored messages can be found in, which excludes forums since their messages are
+/// in the threads of their posts.
+pub const MESSAGE_CHANNEL_TYPES: &[ChannelType] = &[
+    ChannelType::Text,
+    ChannelType::News,
+    ChannelType::NewsThread,
+    ChannelType::PublicThread,
+    ChannelType::PrivateThread,
+];
+
+/// Optionally followed by `:<name>` to register the message as
 pub const ID_MODAL_BOTMSG_SEND: &str = "modal_botmsg_send";
 
 pub const ID_INPUT_CONTENT_MODAL_BOTMSG_SEND: &str = "input_content_modal_botmsg_send";
@@ -24,8 +84,25 @@ pub const ID_INPUT_MSG_MODAL_BOTMSG_EDIT: &str = "input_msg_modal_botmsg_edit";
 pub const ID_INPUT_CHAN_MODAL_BOTMSG_EDIT: &str = "input_chan_modal_botmsg_edit";
 pub const ID_INPUT_CONTENT_MODAL_BOTMSG_EDIT: &str = "input_content_modal_botmsg_edit";
 
+/// Followed by `:<channel id>`, and `:<message id>` when editing or `::<name>` when naming a new
+/// message
+pub const ID_MODAL_BOTMSG_EMBED: &str = "modal_botmsg_embed";
+
+pub const ID_INPUT_TITLE_MODAL_BOTMSG_EMBED: &str = "input_title_modal_botmsg_embed";
+pub const ID_INPUT_DESC_MODAL_BOTMSG_EMBED: &str = "input_desc_modal_botmsg_embed";
+pub const ID_INPUT_FIELDS_MODAL_BOTMSG_EMBED: &str = "input_fields_modal_botmsg_embed";
+pub const ID_INPUT_BUTTONS_MODAL_BOTMSG_EMBED: &str = "input_buttons_modal_botmsg_embed";
+pub const ID_INPUT_STYLE_MODAL_BOTMSG_EMBED: &str = "input_style_modal_botmsg_embed";
+
+/// Followed by `:<channel id>`, and `:<message id>` when editing or `::<name>` when naming a new
+/// message
+pub const ID_MODAL_BOTMSG_JSON: &str = "modal_botmsg_json";
+
+pub const ID_INPUT_JSON_MODAL_BOTMSG_JSON: &str = "input_json_modal_botmsg_json";
+
 pub async fn cmd_botmsg(
     ctx: Context,
+    db: &Database,
     command: ApplicationCommandInteraction,
     admin_role_id: u64,
 ) -> InteractionResult {
@@ -40,66 +117,68 @@ pub async fn cmd_botmsg(
     }
 
     match command.data.options.get(0).map(|o| o.name.as_str()) {
-        Some("send") => cmd_botmsg_send(ctx, command).await,
-        Some("edit") => cmd_botmsg_edit(ctx, command).await,
+        Some("send") => cmd_botmsg_send(ctx, db, command).await,
+        Some("edit") => cmd_botmsg_edit(ctx, db, command).await,
+        Some("embed") => cmd_botmsg_embed(ctx, db, command).await,
+        Some("json") => cmd_botmsg_json(ctx, db, command).await,
+        Some("import") => cmd_botmsg_import(ctx, db, command).await,
+        Some("export") => cmd_botmsg_export(ctx, db, command).await,
+        Some("list") => cmd_botmsg_list(ctx, db, command).await,
+        Some("history") => cmd_botmsg_history(ctx, db, command).await,
+        Some("revert") => cmd_botmsg_revert(ctx, db, command).await,
+        Some("live") => live::cmd_botmsg_live(ctx, db, command).await,
+        Some("freeze") => live::cmd_botmsg_freeze(ctx, db, command).await,
+        Some("roles") => selfroles::cmd_botmsg_roles(ctx, db, command).await,
+        Some("crosspost") => cmd_botmsg_crosspost(ctx, db, command).await,
+        Some("schedule") => schedules::cmd_botmsg_schedule(ctx, command).await,
+        Some("schedules") => schedules::cmd_botmsg_schedules(ctx, db, command).await,
+        Some("reschedule") => schedules::cmd_botmsg_reschedule(ctx, db, command).await,
+        Some("unschedule") => schedules::cmd_botmsg_unschedule(ctx, db, command).await,
         _ => Err(InteractionError::UnprocessableRequest),
     }
 }
 
 async fn cmd_botmsg_send(
     ctx: Context,
+    db: &Database,
     command: ApplicationCommandInteraction,
 ) -> InteractionResult {
-    let channel_id = command
+    let options = &command
         .data
         .options
         .get(0)
-        .and_then(|o| o.options.get(0))
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let channel_id = get_option(options, "channel")
         .and_then(|o| o.value.as_ref())
         .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(ChannelId)
         .ok_or(InteractionError::UnprocessableRequest)?;
 
+    let name = match new_message_name(&ctx, db, &command).await? {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
     command
         .create_interaction_response(&ctx.http, |response| {
             response
                 .kind(InteractionResponseType::Modal)
-                .interaction_response_data(|data| {
-                    data.custom_id(ID_MODAL_BOTMSG_SEND)
-                        .title("Send message as bot")
-                        .components(|components| {
-                            components
-                                .create_action_row(|action_row| {
-                                    action_row.create_input_text(|input_text| {
-                                        input_text
-                                            .custom_id(ID_INPUT_CONTENT_MODAL_BOTMSG_SEND)
-                                            .label("Message content")
-                                            .max_length(2000)
-                                            .required(true)
-                                            .style(InputTextStyle::Paragraph)
-                                    })
-                                })
-                                .create_action_row(|action_row| {
-                                    action_row.create_input_text(|input_text| {
-                                        input_text
-                                            .custom_id(ID_INPUT_CHAN_MODAL_BOTMSG_SEND)
-                                            .label("Channel Id")
-                                            .value(channel_id)
-                                            .max_length(20)
-                                            .required(true)
-                                            .style(InputTextStyle::Short)
-                                    })
-                                })
-                        })
-                })
+                .interaction_response_data(|data| send_modal(data, channel_id, name.as_deref(), ""))
         })
         .await?;
 
     Ok(())
 }
 
+/// Previews a plain bot-authored message, to be registered as `name` if given once sent.
 pub async fn modal_botmsg_send_response(
     ctx: Context,
+    db: &Database,
     interaction: ModalSubmitInteraction,
+    name: Option<&str>,
     admin_role_id: u64,
 ) -> InteractionResult {
     if !interaction
@@ -135,134 +214,55 @@ pub async fn modal_botmsg_send_response(
         .and_then(|v| v.parse::<u64>().ok())
         .ok_or(InteractionError::UnprocessableRequest)?;
 
-    interaction
-        .create_interaction_response(&ctx.http, |response| {
-            response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
-        })
-        .await?;
+    defer_preview(&ctx, &interaction).await?;
 
-    match ChannelId(channel_id)
-        .send_message(&ctx.http, |message| message.content(msg_content))
-        .await
-    {
-        Ok(sent_msg) => {
-            interaction
-                .create_followup_message(&ctx.http, |msg| {
-                    msg.content("Bot-authored message sent!")
-                        .components(|components| {
-                            components.create_action_row(|action_row| {
-                                action_row.create_button(|button| {
-                                    button
-                                        .style(ButtonStyle::Link)
-                                        .label("Go to message")
-                                        .url(sent_msg.link())
-                                })
-                            })
-                        })
-                })
-                .await?
-        }
-        Err(_) => {
-            interaction
-                .create_followup_message(&ctx.http, |msg| {
-                    msg.content("Failed to send bot-authored message!")
-                })
-                .await?
-        }
+    let draft = MessageDraft {
+        content: Some(msg_content.clone()),
+        ..Default::default()
     };
 
-    Ok(())
+    previews::preview_draft(
+        &ctx,
+        db,
+        &interaction,
+        PendingDraft::SEND,
+        (ChannelId(channel_id), None, name),
+        &draft,
+    )
+    .await
 }
 
 async fn cmd_botmsg_edit(
     ctx: Context,
+    db: &Database,
     command: ApplicationCommandInteraction,
 ) -> InteractionResult {
-    let channel_id = command
-        .data
-        .options
-        .get(0)
-        .and_then(|o| o.options.get(0))
-        .and_then(|o| o.value.as_ref())
-        .and_then(|v| v.as_str())
-        .and_then(|v| v.parse::<u64>().ok())
-        .ok_or(InteractionError::UnprocessableRequest)?;
-
-    let msg_id = command
-        .data
-        .options
-        .get(0)
-        .and_then(|o| o.options.get(1))
-        .and_then(|o| o.value.as_ref())
-        .and_then(|v| v.as_str())
-        .and_then(|v| v.parse::<u64>().ok())
-        .ok_or(InteractionError::UnprocessableRequest)?;
+    let (channel_id, msg_id, draft) = match draft_target(&ctx, db, &command).await? {
+        Some((channel_id, Some(msg_id), draft)) => (channel_id, msg_id, draft),
+        _ => return Ok(()),
+    };
 
-    match ChannelId(channel_id).message(&ctx.http, msg_id).await {
-        Ok(msg) => {
-            command
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::Modal)
-                        .interaction_response_data(|data| {
-                            data.custom_id(ID_MODAL_BOTMSG_EDIT)
-                                .title("Edit bot-authored message")
-                                .components(|components| {
-                                    components
-                                        .create_action_row(|action_row| {
-                                            action_row.create_input_text(|input_text| {
-                                                input_text
-                                                    .custom_id(ID_INPUT_CONTENT_MODAL_BOTMSG_EDIT)
-                                                    .label("Message content")
-                                                    .max_length(2000)
-                                                    .value(msg.content)
-                                                    .required(true)
-                                                    .style(InputTextStyle::Paragraph)
-                                            })
-                                        })
-                                        .create_action_row(|action_row| {
-                                            action_row.create_input_text(|input_text| {
-                                                input_text
-                                                    .custom_id(ID_INPUT_CHAN_MODAL_BOTMSG_EDIT)
-                                                    .label("Channel Id")
-                                                    .value(channel_id)
-                                                    .max_length(20)
-                                                    .required(true)
-                                                    .style(InputTextStyle::Short)
-                                            })
-                                        })
-                                        .create_action_row(|action_row| {
-                                            action_row.create_input_text(|input_text| {
-                                                input_text
-                                                    .custom_id(ID_INPUT_MSG_MODAL_BOTMSG_EDIT)
-                                                    .label("Message Id")
-                                                    .value(msg_id)
-                                                    .max_length(20)
-                                                    .required(true)
-                                                    .style(InputTextStyle::Short)
-                                            })
-                                        })
-                                })
-                        })
-                })
-                .await?
-        }
-        Err(_) => {
-            command
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::ChannelMessageWithSource)
-                        .interaction_response_data(|data| data.content("Failed to fetch message!"))
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|data| {
+                    edit_modal(
+                        data,
+                        channel_id,
+                        msg_id,
+                        draft.content.as_deref().unwrap_or(""),
+                    )
                 })
-                .await?
-        }
-    };
+        })
+        .await?;
 
     Ok(())
 }
 
 pub async fn modal_botmsg_edit_response(
     ctx: Context,
+    db: &Database,
     interaction: ModalSubmitInteraction,
     admin_role_id: u64,
 ) -> InteractionResult {
@@ -311,41 +311,1272 @@ pub async fn modal_botmsg_edit_response(
         .and_then(|v| v.parse::<u64>().ok())
         .ok_or(InteractionError::UnprocessableRequest)?;
 
-    interaction
-        .create_interaction_response(&ctx.http, |response| {
-            response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
-        })
-        .await?;
+    defer_preview(&ctx, &interaction).await?;
 
-    match ChannelId(channel_id)
-        .edit_message(&ctx.http, msg_id, |msg| msg.content(msg_content))
+    // Only the content is replaced, the rest of the message is kept as it is
+    let draft = match ChannelId(channel_id)
+        .message(&ctx.http, MessageId(msg_id))
         .await
     {
-        Ok(edited_msg) => {
+        Ok(msg) => MessageDraft {
+            content: Some(msg_content.clone()),
+            ..MessageDraft::from_message(&msg)
+        },
+        Err(why) => {
             interaction
                 .create_followup_message(&ctx.http, |msg| {
-                    msg.content("Bot-authored message edited!")
-                        .components(|components| {
-                            components.create_action_row(|action_row| {
-                                action_row.create_button(|button| {
-                                    button
-                                        .style(ButtonStyle::Link)
-                                        .label("Go to message")
-                                        .url(edited_msg.link())
-                                })
-                            })
-                        })
+                    msg.content(format!("Failed to fetch message: {}", why))
+                })
+                .await?;
+            return Ok(());
+        }
+    };
+
+    previews::preview_draft(
+        &ctx,
+        db,
+        &interaction,
+        PendingDraft::EDIT,
+        (ChannelId(channel_id), Some(MessageId(msg_id)), None),
+        &draft,
+    )
+    .await
+}
+
+/// Opens the guided modal for authoring an embed with link buttons, either to send as a new
+/// message or, if `message` is given, to replace the first embed and buttons of an existing one.
+async fn cmd_botmsg_embed(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let (channel_id, msg_id, draft) = match draft_target(&ctx, db, &command).await? {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let name = match msg_id {
+        Some(_) => None,
+        None => match new_message_name(&ctx, db, &command).await? {
+            Some(name) => name,
+            None => return Ok(()),
+        },
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|data| {
+                    embed_modal(data, channel_id, msg_id, name.as_deref(), &draft)
                 })
-                .await?
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Opens a modal taking a whole message as Discord message JSON, to send as a new message or, if
+/// `message` is given, to overwrite an existing one.
+async fn cmd_botmsg_json(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let (channel_id, msg_id, draft) = match draft_target(&ctx, db, &command).await? {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let name = match msg_id {
+        Some(_) => None,
+        None => match new_message_name(&ctx, db, &command).await? {
+            Some(name) => name,
+            None => return Ok(()),
+        },
+    };
+
+    // Messages too big for the modal can't be prefilled
+    let json = match msg_id {
+        Some(_) => Some(draft.to_json()).filter(|json| json.chars().count() <= MAX_INPUT_LENGTH),
+        None => None,
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|data| {
+                    json_modal(data, channel_id, msg_id, name.as_deref(), json.as_deref())
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn modal_botmsg_embed_response(
+    ctx: Context,
+    db: &Database,
+    interaction: ModalSubmitInteraction,
+    target: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let (channel_id, msg_id, name) = parse_draft_target(target)?;
+
+    let rows = &interaction.data.components;
+    let form = [0, 1, 2, 3, 4].map(|i| get_input_value(rows, i).unwrap_or(""));
+
+    defer_preview(&ctx, &interaction).await?;
+
+    // Editing keeps the content and any other embeds of the message
+    let base = match msg_id {
+        Some(msg_id) => match channel_id.message(&ctx.http, msg_id).await {
+            Ok(msg) => MessageDraft::from_message(&msg),
+            Err(why) => {
+                interaction
+                    .create_followup_message(&ctx.http, |msg| {
+                        msg.content(format!("Failed to fetch message: {}", why))
+                    })
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => MessageDraft::default(),
+    };
+
+    match base.with_form(form[0], form[1], form[2], form[3], form[4]) {
+        Ok(draft) => {
+            let target = (channel_id, msg_id, name);
+            previews::preview_draft(&ctx, db, &interaction, PendingDraft::EMBED, target, &draft)
+                .await
         }
-        Err(_) => {
+        Err(why) => {
             interaction
-                .create_followup_message(&ctx.http, |msg| {
-                    msg.content("Failed edit bot-authored message!")
+                .create_followup_message(&ctx.http, |msg| msg.content(why))
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+pub async fn modal_botmsg_json_response(
+    ctx: Context,
+    db: &Database,
+    interaction: ModalSubmitInteraction,
+    target: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let (channel_id, msg_id, name) = parse_draft_target(target)?;
+
+    let json = get_input_value(&interaction.data.components, 0)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    defer_preview(&ctx, &interaction).await?;
+
+    match MessageDraft::from_json(json) {
+        Ok(draft) => {
+            let target = (channel_id, msg_id, name);
+            previews::preview_draft(&ctx, db, &interaction, PendingDraft::JSON, target, &draft)
+                .await
+        }
+        Err(why) => {
+            interaction
+                .create_followup_message(&ctx.http, |msg| msg.content(why))
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+/// Sends or overwrites a message from an attached Discord or Discohook message JSON file.
+async fn cmd_botmsg_import(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let attachment = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "file"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .and_then(|id| command.data.resolved.attachments.get(&AttachmentId(id)))
+        .cloned()
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let (channel_id, msg_id, _) = match draft_target(&ctx, db, &command).await? {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let name = match msg_id {
+        Some(_) => None,
+        None => match new_message_name(&ctx, db, &command).await? {
+            Some(name) => name,
+            None => return Ok(()),
+        },
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags))
+        })
+        .await?;
+
+    let draft = if attachment.size > MAX_IMPORT_SIZE {
+        Err(format!(
+            "The file is too big, the most allowed is {} KiB!",
+            MAX_IMPORT_SIZE / 1024
+        ))
+    } else {
+        match attachment.download().await {
+            Ok(bytes) => String::from_utf8(bytes)
+                .map_err(|_| "The file isn't valid UTF-8 text!".to_string())
+                .and_then(|json| MessageDraft::from_json(&json)),
+            Err(why) => Err(format!("Failed to download the file: {}", why)),
+        }
+    };
+
+    match draft {
+        Ok(draft) => {
+            let role_menu = RoleMenu::get(db, msg_id)?;
+            let result = send_draft(
+                &ctx.http,
+                db,
+                channel_id,
+                msg_id,
+                &draft,
+                role_menu.as_ref(),
+                MENTIONS_NONE,
+            )
+            .await;
+            if let Ok(msg) = &result {
+                record_revision(db, msg, name.as_deref(), command.user.id.0)?;
+            }
+
+            command
+                .create_followup_message(&ctx.http, |followup| {
+                    delivery_followup(followup, msg_id, result)
+                })
+                .await?;
+        }
+        Err(why) => {
+            command
+                .create_followup_message(&ctx.http, |followup| followup.content(why))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Dumps a bot-authored message as a Discord message JSON file, which can be loaded into
+/// Discohook or imported again with `/botmsg import`.
+async fn cmd_botmsg_export(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let (_, msg_id, draft) = match draft_target(&ctx, db, &command).await? {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+    let msg_id = msg_id.ok_or(InteractionError::UnprocessableRequest)?;
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags))
+        })
+        .await?;
+
+    let json = draft.to_json();
+
+    command
+        .create_followup_message(&ctx.http, |followup| {
+            followup
+                .content("Exported bot-authored message!")
+                .add_file((json.as_bytes(), format!("message-{}.json", msg_id).as_str()))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Sets whether bot-authored messages sent to an announcement channel are published to the
+/// servers following it.
+async fn cmd_botmsg_crosspost(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let channel_id = get_option(options, "channel")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let enabled = get_option(options, "enabled")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_bool())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let is_news = matches!(
+        command.data.resolved.channels.get(&ChannelId(channel_id)),
+        Some(channel) if channel.kind == ChannelType::News
+    );
+
+    let content = if !is_news {
+        format!(
+            "{} isn't an announcement channel, only their messages can be published!",
+            ChannelId(channel_id).mention()
+        )
+    } else {
+        CrosspostChannel::set(db, channel_id, enabled)?;
+
+        if enabled {
+            format!(
+                "Bot-authored messages sent to {} will be published to following servers.",
+                ChannelId(channel_id).mention()
+            )
+        } else {
+            format!(
+                "Bot-authored messages sent to {} will no longer be published.",
+                ChannelId(channel_id).mention()
+            )
+        }
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags).content(content))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Lists every registered bot-authored message.
+async fn cmd_botmsg_list(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let messages = BotMessage::get_all(db)?;
+
+    let description = if messages.is_empty() {
+        "No bot-authored messages have been sent yet.".to_string()
+    } else {
+        messages
+            .iter()
+            .map(|message| {
+                let revisions = BotMessageRevision::get_by_message(db, message.id)?;
+
+                Ok(format!(
+                    "`{}` in {} by {}, {} revision{}, last edited <t:{}:R>",
+                    message.name,
+                    ChannelId(message.channel_id).mention(),
+                    UserId(message.created_by).mention(),
+                    revisions.len(),
+                    if revisions.len() == 1 { "" } else { "s" },
+                    revisions
+                        .first()
+                        .map_or(message.created_at, |revision| revision.created_at)
+                ))
+            })
+            .collect::<Result<Vec<_>, InteractionError>>()?
+            .join("\n")
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Bot-authored messages")
+                            .description(truncate(&description, 4096))
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Lists the latest revisions of a registered message, to pick one for `/botmsg revert`.
+async fn cmd_botmsg_history(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let message = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "message"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let (title, description) = match BotMessage::get_by_name(db, message.trim())? {
+        Some(message) => {
+            let revisions = BotMessageRevision::get_by_message(db, message.id)?;
+
+            (
+                format!("History of {}", message.name),
+                revisions
+                    .iter()
+                    .take(HISTORY_LENGTH)
+                    .map(|revision| {
+                        format!(
+                            "`#{}` <t:{}:R> by {}: {}",
+                            revision.id,
+                            revision.created_at,
+                            UserId(revision.actor_id).mention(),
+                            revision_summary(revision)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        }
+        None => (
+            "Unknown message".to_string(),
+            format!("No bot-authored message named `{}`!", message),
+        ),
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags)
+                        .embed(|embed| embed.title(title).description(description))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Restores a registered message to one of its past revisions, which is recorded as a new revision
+/// so the revert can itself be undone.
+async fn cmd_botmsg_revert(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let message = get_option(options, "message")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let revision_id = get_option(options, "revision")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags))
+        })
+        .await?;
+
+    let target = match BotMessage::get_by_name(db, message.trim())? {
+        Some(message) => match BotMessageRevision::get_by_id(db, revision_id)? {
+            Some(revision) if revision.bot_message_id == message.id => {
+                MessageDraft::from_json(&revision.json).map(|draft| (message, draft))
+            }
+            _ => Err(format!(
+                "`{}` has no revision `#{}`, see `/botmsg history`!",
+                message.name, revision_id
+            )),
+        },
+        None => Err(format!("No bot-authored message named `{}`!", message)),
+    };
+
+    match target {
+        Ok((message, draft)) => {
+            let msg_id = Some(MessageId(message.message_id));
+            let role_menu = RoleMenu::for_message(db, &message)?;
+            let result = send_draft(
+                &ctx.http,
+                db,
+                ChannelId(message.channel_id),
+                msg_id,
+                &draft,
+                role_menu.as_ref(),
+                MENTIONS_NONE,
+            )
+            .await;
+            if let Ok(msg) = &result {
+                record_revision(db, msg, None, command.user.id.0)?;
+            }
+
+            command
+                .create_followup_message(&ctx.http, |followup| {
+                    delivery_followup(followup, msg_id, result)
+                })
+                .await?;
+        }
+        Err(why) => {
+            command
+                .create_followup_message(&ctx.http, |followup| followup.content(why))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Suggests registered bot-authored messages by name.
+pub async fn autocomplete_botmsg(
+    ctx: Context,
+    db: &Database,
+    autocomplete: AutocompleteInteraction,
+) -> InteractionResult {
+    let typed = autocomplete
+        .data
+        .options
+        .get(0)
+        .and_then(|subcommand| subcommand.options.iter().find(|o| o.focused))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    // Discord allows at most 25 choices
+    let names: Vec<String> = BotMessage::get_all(db)?
+        .into_iter()
+        .filter(|m| m.name.contains(&typed))
+        .take(25)
+        .map(|m| m.name)
+        .collect();
+
+    autocomplete
+        .create_autocomplete_response(&ctx.http, |response| {
+            for name in names {
+                response.add_string_choice(&name, &name);
+            }
+            response
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// A short description of what a revision looked like, for listing revisions.
+fn revision_summary(revision: &BotMessageRevision) -> String {
+    let draft: MessageDraft = match serde_json::from_str(&revision.json) {
+        Ok(draft) => draft,
+        Err(_) => return "(unreadable)".to_string(),
+    };
+
+    let summary = draft
+        .content
+        .clone()
+        .or_else(|| draft.embeds.get(0).and_then(|e| e.title.clone()))
+        .or_else(|| draft.embeds.get(0).and_then(|e| e.description.clone()))
+        .unwrap_or_else(|| "(empty)".to_string());
+
+    truncate(&summary.replace('\n', " "), 50)
+}
+
+/// Finds where a `/botmsg` subcommand sends to or edits, from its `message` option (the name of a
+/// registered message, or the id of one in the `channel` option's channel) or else its `channel`
+/// option, and fetches the message being edited.
+///
+/// Responds with what was wrong and returns `None` if there is no such message.
+async fn draft_target(
+    ctx: &Context,
+    db: &Database,
+    command: &ApplicationCommandInteraction,
+) -> Result<Option<(ChannelId, Option<MessageId>, MessageDraft)>, InteractionError> {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let channel_id = get_option(options, "channel")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(ChannelId);
+
+    let message = get_option(options, "message")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(str::trim);
+
+    let target = match message {
+        Some(message) => {
+            let registered = match message.parse::<u64>() {
+                Ok(msg_id) => BotMessage::get_by_message_id(db, msg_id)?,
+                Err(_) => BotMessage::get_by_name(db, message)?,
+            };
+
+            match (registered, message.parse::<u64>(), channel_id) {
+                (Some(registered), _, _) => Ok((
+                    ChannelId(registered.channel_id),
+                    Some(MessageId(registered.message_id)),
+                )),
+                (None, Ok(msg_id), Some(channel_id)) => Ok((channel_id, Some(MessageId(msg_id)))),
+                (None, Ok(_), None) => {
+                    Err("Give the channel of messages that aren't registered!".to_string())
+                }
+                (None, Err(_), _) => Err(format!("No bot-authored message named `{}`!", message)),
+            }
+        }
+        None => channel_id
+            .map(|channel_id| (channel_id, None))
+            .ok_or_else(|| "Give a channel to send to or a message to edit!".to_string()),
+    };
+
+    let target = match target {
+        Ok((channel_id, Some(msg_id))) => match channel_id.message(&ctx.http, msg_id).await {
+            Ok(msg) => Ok((channel_id, Some(msg_id), MessageDraft::from_message(&msg))),
+            Err(_) => Err("Failed to fetch message!".to_string()),
+        },
+        Ok((channel_id, None)) => Ok((channel_id, None, MessageDraft::default())),
+        Err(why) => Err(why),
+    };
+
+    match target {
+        Ok(target) => Ok(Some(target)),
+        Err(why) => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| data.flags(flags).content(why))
                 })
-                .await?
+                .await?;
+
+            Ok(None)
+        }
+    }
+}
+
+/// Reads and checks the optional `name` option given when sending a new message.
+///
+/// Responds with what was wrong and returns `None` if the name can't be used.
+pub async fn new_message_name(
+    ctx: &Context,
+    db: &Database,
+    command: &ApplicationCommandInteraction,
+) -> Result<Option<Option<String>>, InteractionError> {
+    let name = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "name"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_lowercase());
+
+    let problem = match &name {
+        None => None,
+        Some(name)
+            if name.is_empty()
+                || name.len() > MAX_NAME_LENGTH
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                || name.parse::<u64>().is_ok() =>
+        {
+            Some(format!(
+                "Names must be at most {} letters, digits, `-` or `_`, and not just digits!",
+                MAX_NAME_LENGTH
+            ))
+        }
+        Some(name) => BotMessage::get_by_name(db, name)?
+            .map(|_| format!("There is already a bot-authored message named `{}`!", name)),
+    };
+
+    match problem {
+        None => Ok(Some(name)),
+        Some(why) => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| data.flags(flags).content(why))
+                })
+                .await?;
+
+            Ok(None)
+        }
+    }
+}
+
+/// Builds the custom id of a drafting modal, carrying the channel and either the message being
+/// edited or the name to register a new message as.
+fn draft_custom_id(
+    id: &str,
+    channel_id: ChannelId,
+    msg_id: Option<MessageId>,
+    name: Option<&str>,
+) -> String {
+    match (msg_id, name) {
+        (Some(msg_id), _) => format!("{}:{}:{}", id, channel_id, msg_id),
+        (None, Some(name)) => format!("{}:{}::{}", id, channel_id, name),
+        (None, None) => format!("{}:{}", id, channel_id),
+    }
+}
+
+/// Parses the `<channel id>[:<message id>]` or `<channel id>::<name>` argument of a drafting
+/// modal's custom id.
+#[allow(clippy::type_complexity)]
+fn parse_draft_target(
+    target: &str,
+) -> Result<(ChannelId, Option<MessageId>, Option<&str>), InteractionError> {
+    let mut parts = target.splitn(3, ':');
+
+    let channel_id = parts
+        .next()
+        .and_then(|id| id.parse::<u64>().ok())
+        .map(ChannelId)
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let msg_id = match parts.next() {
+        Some("") | None => None,
+        Some(msg_id) => Some(MessageId(
+            msg_id
+                .parse::<u64>()
+                .map_err(|_| InteractionError::UnprocessableRequest)?,
+        )),
+    };
+
+    Ok((channel_id, msg_id, parts.next()))
+}
+
+/// Records the current state of a message the bot just sent or edited as a new revision,
+/// registering the message first (as `name`, if given) if it isn't already.
+pub fn record_revision(
+    db: &Database,
+    msg: &Message,
+    name: Option<&str>,
+    actor_id: u64,
+) -> Result<(), InteractionError> {
+    let bot_message_id = match BotMessage::get_by_message_id(db, msg.id.0)? {
+        Some(registered) => registered.id,
+        None => {
+            // The name was checked when the command was run, but could have been taken since
+            let name = match name {
+                Some(name) if BotMessage::get_by_name(db, name)?.is_none() => Some(name),
+                _ => None,
+            };
+            BotMessage::create(db, name, msg.channel_id.0, msg.id.0, actor_id)?
         }
     };
 
+    BotMessageRevision::create(
+        db,
+        bot_message_id,
+        &MessageDraft::from_message(msg).to_json(),
+        actor_id,
+    )?;
+
+    Ok(())
+}
+
+/// Records in the audit log that `actor_id` pinged everyone or some roles with `msg`, if they did.
+pub fn record_pings(db: &Database, msg: &Message, actor_id: u64) -> Result<(), InteractionError> {
+    let mut pinged = Vec::new();
+    if msg.mention_everyone {
+        pinged.extend(
+            ["@everyone", "@here"]
+                .into_iter()
+                .filter(|everyone| msg.content.contains(everyone))
+                .map(str::to_string),
+        );
+    }
+    pinged.extend(
+        msg.mention_roles
+            .iter()
+            .map(|role| role.mention().to_string()),
+    );
+
+    if !pinged.is_empty() {
+        AuditLog::record(
+            db,
+            actor_id,
+            AuditLog::MESSAGE_PING,
+            msg.id.0 as i64,
+            None,
+            Some(&pinged.join(" ")),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Lists who sending `draft` with `mentions` allowed would ping, other than individual users.
+pub fn mass_mentions(draft: &MessageDraft, mentions: &str) -> Vec<String> {
+    let content = draft.content.as_deref().unwrap_or("");
+    let mut pinged = Vec::new();
+
+    if mentions == MENTIONS_EVERYONE {
+        pinged.extend(
+            ["@everyone", "@here"]
+                .into_iter()
+                .filter(|everyone| content.contains(everyone))
+                .map(str::to_string),
+        );
+    }
+
+    if mentions != MENTIONS_NONE {
+        for (i, _) in content.match_indices("<@&") {
+            let role = content[i + 3..]
+                .split_once('>')
+                .and_then(|(id, _)| id.parse::<u64>().ok())
+                .map(|id| RoleId(id).mention().to_string());
+            if let Some(role) = role {
+                if !pinged.contains(&role) {
+                    pinged.push(role);
+                }
+            }
+        }
+    }
+
+    pinged
+}
+
+/// The kinds of mention that ping when a message is sent with `mentions` allowed.
+fn parsed_mentions(mentions: &str) -> Vec<ParseValue> {
+    match mentions {
+        MENTIONS_EVERYONE => vec![ParseValue::Users, ParseValue::Roles, ParseValue::Everyone],
+        MENTIONS_ROLES => vec![ParseValue::Users, ParseValue::Roles],
+        _ => Vec::new(),
+    }
+}
+
+fn allow_mentions<'a>(
+    allowed: &'a mut CreateAllowedMentions,
+    mentions: &str,
+) -> &'a mut CreateAllowedMentions {
+    allowed.empty_parse();
+    for value in parsed_mentions(mentions) {
+        allowed.parse(value);
+    }
+    allowed
+}
+
+/// Responds to a modal submission with an ephemeral deferral, followed up on by the preview.
+pub async fn defer_preview(
+    ctx: &Context,
+    interaction: &ModalSubmitInteraction,
+) -> InteractionResult {
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                })
+        })
+        .await?;
+
     Ok(())
 }
+
+/// Fills in the modal for sending a plain message with `content` to `channel_id`.
+pub fn send_modal<'a, 'b>(
+    data: &'b mut CreateInteractionResponseData<'a>,
+    channel_id: ChannelId,
+    name: Option<&str>,
+    content: &str,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    let custom_id = match name {
+        Some(name) => format!("{}:{}", ID_MODAL_BOTMSG_SEND, name),
+        None => ID_MODAL_BOTMSG_SEND.to_string(),
+    };
+
+    data.custom_id(custom_id)
+        .title("Send message as bot")
+        .components(|components| {
+            components
+                .create_action_row(|action_row| {
+                    action_row.create_input_text(|input_text| {
+                        input_text
+                            .custom_id(ID_INPUT_CONTENT_MODAL_BOTMSG_SEND)
+                            .label("Message content")
+                            .max_length(2000)
+                            .value(content)
+                            .required(true)
+                            .style(InputTextStyle::Paragraph)
+                    })
+                })
+                .create_action_row(|action_row| {
+                    action_row.create_input_text(|input_text| {
+                        input_text
+                            .custom_id(ID_INPUT_CHAN_MODAL_BOTMSG_SEND)
+                            .label("Channel Id")
+                            .value(channel_id)
+                            .max_length(20)
+                            .required(true)
+                            .style(InputTextStyle::Short)
+                    })
+                })
+        })
+}
+
+/// Fills in the modal for replacing the content of the message `msg_id` with `content`.
+pub fn edit_modal<'a, 'b>(
+    data: &'b mut CreateInteractionResponseData<'a>,
+    channel_id: ChannelId,
+    msg_id: MessageId,
+    content: &str,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    data.custom_id(ID_MODAL_BOTMSG_EDIT)
+        .title("Edit bot-authored message")
+        .components(|components| {
+            components
+                .create_action_row(|action_row| {
+                    action_row.create_input_text(|input_text| {
+                        input_text
+                            .custom_id(ID_INPUT_CONTENT_MODAL_BOTMSG_EDIT)
+                            .label("Message content")
+                            .max_length(2000)
+                            .value(content)
+                            .required(true)
+                            .style(InputTextStyle::Paragraph)
+                    })
+                })
+                .create_action_row(|action_row| {
+                    action_row.create_input_text(|input_text| {
+                        input_text
+                            .custom_id(ID_INPUT_CHAN_MODAL_BOTMSG_EDIT)
+                            .label("Channel Id")
+                            .value(channel_id)
+                            .max_length(20)
+                            .required(true)
+                            .style(InputTextStyle::Short)
+                    })
+                })
+                .create_action_row(|action_row| {
+                    action_row.create_input_text(|input_text| {
+                        input_text
+                            .custom_id(ID_INPUT_MSG_MODAL_BOTMSG_EDIT)
+                            .label("Message Id")
+                            .value(msg_id)
+                            .max_length(20)
+                            .required(true)
+                            .style(InputTextStyle::Short)
+                    })
+                })
+        })
+}
+
+/// Fills in the guided modal for authoring the first embed and link buttons of `draft`.
+pub fn embed_modal<'a, 'b>(
+    data: &'b mut CreateInteractionResponseData<'a>,
+    channel_id: ChannelId,
+    msg_id: Option<MessageId>,
+    name: Option<&str>,
+    draft: &MessageDraft,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    let [title, description, fields, buttons, style] = draft.form();
+
+    data.custom_id(draft_custom_id(
+        ID_MODAL_BOTMSG_EMBED,
+        channel_id,
+        msg_id,
+        name,
+    ))
+    .title("Author embed as bot")
+    .components(|components| {
+        components
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_TITLE_MODAL_BOTMSG_EMBED)
+                        .label("Title")
+                        .value(truncate(&title, 256))
+                        .max_length(256)
+                        .required(false)
+                        .style(InputTextStyle::Short)
+                })
+            })
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_DESC_MODAL_BOTMSG_EMBED)
+                        .label("Description")
+                        .value(truncate(&description, MAX_INPUT_LENGTH))
+                        .max_length(MAX_INPUT_LENGTH as u64)
+                        .required(false)
+                        .style(InputTextStyle::Paragraph)
+                })
+            })
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_FIELDS_MODAL_BOTMSG_EMBED)
+                        .label("Fields (name | value [| inline] per line)")
+                        .value(truncate(&fields, MAX_INPUT_LENGTH))
+                        .max_length(MAX_INPUT_LENGTH as u64)
+                        .required(false)
+                        .style(InputTextStyle::Paragraph)
+                })
+            })
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_BUTTONS_MODAL_BOTMSG_EMBED)
+                        .label("Link buttons (label | url per line)")
+                        .value(truncate(&buttons, MAX_INPUT_LENGTH))
+                        .max_length(MAX_INPUT_LENGTH as u64)
+                        .required(false)
+                        .style(InputTextStyle::Paragraph)
+                })
+            })
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_STYLE_MODAL_BOTMSG_EMBED)
+                        .label("Style (key: value per line)")
+                        .placeholder(
+                            "colour: #5865F2\nfooter: text\nimage: https://…\n\
+                                thumbnail, author, url",
+                        )
+                        .value(truncate(&style, MAX_INPUT_LENGTH))
+                        .max_length(MAX_INPUT_LENGTH as u64)
+                        .required(false)
+                        .style(InputTextStyle::Paragraph)
+                })
+            })
+    })
+}
+
+/// Fills in the modal taking a whole message as Discord message JSON, prefilled with `json`.
+pub fn json_modal<'a, 'b>(
+    data: &'b mut CreateInteractionResponseData<'a>,
+    channel_id: ChannelId,
+    msg_id: Option<MessageId>,
+    name: Option<&str>,
+    json: Option<&str>,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    data.custom_id(draft_custom_id(
+        ID_MODAL_BOTMSG_JSON,
+        channel_id,
+        msg_id,
+        name,
+    ))
+    .title("Send message JSON as bot")
+    .components(|components| {
+        components.create_action_row(|action_row| {
+            action_row.create_input_text(|input_text| {
+                input_text
+                    .custom_id(ID_INPUT_JSON_MODAL_BOTMSG_JSON)
+                    .label("Message JSON")
+                    .placeholder(r#"{"content": "…", "embeds": [{"title": "…"}]}"#)
+                    .max_length(MAX_INPUT_LENGTH as u64)
+                    .required(true)
+                    .style(InputTextStyle::Paragraph);
+                if let Some(json) = &json {
+                    input_text.value(json);
+                }
+                input_text
+            })
+        })
+    })
+}
+
+/// Sends `draft` to `channel_id`, or overwrites the message `msg_id` with it, followed by the
+/// roles offered under the message if it has a `role_menu`.
+///
+/// New messages can be sent to text and announcement channels, threads, and forums, where they
+/// start a new post. Those sent to announcement channels with crossposting enabled are published
+/// to following servers too.
+pub async fn send_draft(
+    http: &Http,
+    db: &Database,
+    channel_id: ChannelId,
+    msg_id: Option<MessageId>,
+    draft: &MessageDraft,
+    role_menu: Option<&RoleMenu>,
+    mentions: &str,
+) -> serenity::Result<Message> {
+    let mut components = draft.create_components();
+    if let Some(role_menu) = role_menu {
+        role_menu.create_rows(&mut components);
+    }
+
+    if let Some(msg_id) = msg_id {
+        return channel_id
+            .edit_message(http, msg_id, |msg| {
+                msg.content(draft.content.as_deref().unwrap_or(""))
+                    .set_embeds(draft.create_embeds())
+                    .set_components(components)
+                    .allowed_mentions(|allowed| allow_mentions(allowed, mentions))
+            })
+            .await;
+    }
+
+    let channel = channel_id
+        .to_channel(http)
+        .await?
+        .guild()
+        .ok_or(serenity::Error::Other(
+            "Bot-authored messages can only be sent to channels of this server!",
+        ))?;
+
+    match channel.kind {
+        ChannelType::Forum => {
+            let title = draft.post_title().ok_or(serenity::Error::Other(
+                "Forum posts need a title, give the message an embed title or some content!",
+            ))?;
+
+            let mut message = serde_json::to_value(draft)?;
+            message["components"] = Value::Array(components.0);
+            message["allowed_mentions"] = serde_json::json!({ "parse": parsed_mentions(mentions) });
+
+            let mut post = serde_json::Map::new();
+            post.insert("name".to_string(), Value::String(title));
+            post.insert("message".to_string(), message);
+
+            // Forum posts are created through the same endpoint as threads without a starting
+            // message, and their first message has the same id as the post itself
+            let post = http.create_private_thread(channel_id.0, &post).await?;
+            post.id.message(http, MessageId(post.id.0)).await
+        }
+        ChannelType::Text
+        | ChannelType::News
+        | ChannelType::NewsThread
+        | ChannelType::PublicThread
+        | ChannelType::PrivateThread => {
+            if matches!(&channel.thread_metadata, Some(metadata) if metadata.locked) {
+                return Err(serenity::Error::Other(
+                    "That thread is locked, unlock it to send to it!",
+                ));
+            }
+
+            let msg = channel_id
+                .send_message(http, |msg| {
+                    if let Some(content) = &draft.content {
+                        msg.content(content);
+                    }
+                    msg.set_embeds(draft.create_embeds())
+                        .set_components(components)
+                        .allowed_mentions(|allowed| allow_mentions(allowed, mentions))
+                })
+                .await?;
+
+            if channel.kind == ChannelType::News {
+                match CrosspostChannel::is_enabled(db, channel_id.0) {
+                    Ok(true) => {
+                        // The message was still sent, so this is only logged
+                        if let Err(why) = channel_id.crosspost(http, msg.id).await {
+                            println!("Error when crossposting message {}: {:?}", msg.id, why);
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(why) => println!("Error when checking crossposting: {:?}", why),
+                }
+            }
+
+            Ok(msg)
+        }
+        _ => Err(serenity::Error::Other(
+            "Bot-authored messages can only be sent to text, announcement, thread or forum \
+            channels!",
+        )),
+    }
+}
+
+/// Builds the followup reporting the outcome of [`send_draft`].
+pub fn delivery_followup<'a, 'b>(
+    followup: &'b mut CreateInteractionResponseFollowup<'a>,
+    msg_id: Option<MessageId>,
+    result: serenity::Result<Message>,
+) -> &'b mut CreateInteractionResponseFollowup<'a> {
+    match result {
+        Ok(msg) => followup
+            .content(if msg_id.is_some() {
+                "Bot-authored message edited!"
+            } else {
+                "Bot-authored message sent!"
+            })
+            .components(|components| {
+                components.create_action_row(|action_row| {
+                    action_row.create_button(|button| {
+                        button
+                            .style(ButtonStyle::Link)
+                            .label("Go to message")
+                            .url(msg.link())
+                    })
+                })
+            }),
+        Err(why) => followup.content(format!(
+            "Failed to {} bot-authored message: {}",
+            if msg_id.is_some() { "edit" } else { "send" },
+            why
+        )),
+    }
+}
@@ -0,0 +1,667 @@
+use serenity::{
+    builder::CreateComponents,
+    client::Context,
+    model::{
+        id::{ChannelId, MessageId, RoleId},
+        interactions::{
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            modal::ModalSubmitInteraction,
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{PendingDraft, ScheduledMessage, Setting},
+        Database,
+    },
+    drafts::MessageDraft,
+    messages::{
+        delivery_followup, edit_modal, embed_modal, json_modal, mass_mentions, record_pings,
+        record_revision, send_draft, send_modal, MAX_INPUT_LENGTH, MENTIONS_EVERYONE,
+        MENTIONS_NONE, MENTIONS_ROLES,
+    },
+    response_flags,
+    schedules::{schedule_modal, schedule_pending},
+    selfroles::RoleMenu,
+    truncate, InteractionError, InteractionResult,
+};
+
+/// Followed by `:<pending draft id>`
+pub const ID_BUTTON_BOTMSG_CONFIRM: &str = "button_botmsg_confirm";
+/// Followed by `:<pending draft id>`
+pub const ID_BUTTON_BOTMSG_REVISE: &str = "button_botmsg_revise";
+/// Followed by `:<pending draft id>`
+pub const ID_BUTTON_BOTMSG_CANCEL: &str = "button_botmsg_cancel";
+/// Followed by `:<pending draft id>`, confirms sending a draft that pings everyone or some roles
+pub const ID_BUTTON_BOTMSG_PING: &str = "button_botmsg_ping";
+/// Followed by `:<pending draft id>`
+pub const ID_SELECT_BOTMSG_MENTIONS: &str = "select_botmsg_mentions";
+
+/// The levels of allowed mentions a draft can be sent with, and how they are described.
+const MENTION_OPTIONS: [(&str, &str); 3] = [
+    (MENTIONS_NONE, "Ping nobody"),
+    (MENTIONS_ROLES, "Ping the users and roles mentioned"),
+    (
+        MENTIONS_EVERYONE,
+        "Ping everyone mentioned, @everyone and @here too",
+    ),
+];
+
+/// The longest each section of a diff is shown, so both fit in an embed's description.
+const MAX_DIFF_LENGTH: usize = 1800;
+
+/// How many unchanged lines are shown around each change.
+const DIFF_CONTEXT: usize = 2;
+
+/// Diffs of versions with more lines than this multiplied together aren't worked out line by
+/// line, and are shown as everything being replaced instead.
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// Follows up on a modal submission, deferred ephemerally, with a preview of `draft`, a choice of
+/// who it may ping and buttons to send it, edit it further or cancel. Until then the draft is kept
+/// in the database.
+///
+/// `target` is the channel to send to, the message to overwrite, if any, and the name to register a
+/// new message as, if any.
+pub async fn preview_draft(
+    ctx: &Context,
+    db: &Database,
+    interaction: &ModalSubmitInteraction,
+    kind: &str,
+    target: (ChannelId, Option<MessageId>, Option<&str>),
+    draft: &MessageDraft,
+) -> InteractionResult {
+    let (channel_id, msg_id, name) = target;
+
+    let current = match msg_id {
+        Some(msg_id) => match channel_id.message(&ctx.http, msg_id).await {
+            Ok(msg) => Some(msg),
+            Err(why) => {
+                interaction
+                    .create_followup_message(&ctx.http, |msg| {
+                        msg.content(format!("Failed to fetch message: {}", why))
+                    })
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let id = PendingDraft::create(
+        db,
+        kind,
+        interaction.user.id.0,
+        channel_id.0,
+        msg_id.map(|msg_id| msg_id.0),
+        name,
+        &draft.to_json(),
+    )?;
+
+    let description = match &current {
+        Some(msg) => format!(
+            "Editing {}\n\n{}",
+            msg.link(),
+            changes(&MessageDraft::from_message(msg), draft)
+        ),
+        None => format!(
+            "Sending to {}{}",
+            channel_id.mention(),
+            name.map(|name| format!(" as `{}`", name))
+                .unwrap_or_default()
+        ),
+    };
+
+    show_preview(ctx, interaction, id, draft, description, MENTIONS_NONE).await
+}
+
+/// Like [`preview_draft`], for a draft that is scheduled rather than sent once confirmed.
+///
+/// `schedule` is the time it is first sent, the cron expression it then repeats on, if any, and
+/// the scheduled message it replaces, if any, whose choice of who to ping it starts out with.
+pub async fn preview_schedule(
+    ctx: &Context,
+    db: &Database,
+    interaction: &ModalSubmitInteraction,
+    channel_id: ChannelId,
+    schedule: (i64, Option<&str>, Option<&ScheduledMessage>),
+    draft: &MessageDraft,
+) -> InteractionResult {
+    let (send_at, cron, scheduled) = schedule;
+    let user_id = interaction.user.id.0;
+
+    let id = PendingDraft::create(
+        db,
+        PendingDraft::SCHEDULE,
+        user_id,
+        channel_id.0,
+        None,
+        None,
+        &draft.to_json(),
+    )?;
+    PendingDraft::set_schedule(
+        db,
+        id,
+        send_at,
+        cron,
+        scheduled.map(|scheduled| scheduled.id),
+    )?;
+
+    let mentions = scheduled.map_or(MENTIONS_NONE, |scheduled| &scheduled.mentions);
+    PendingDraft::set_mentions(db, id, user_id, mentions)?;
+
+    let description = format!(
+        "{} {} <t:{}:F> (<t:{}:R>){}",
+        match scheduled {
+            Some(scheduled) => format!("Rescheduling `#{}` for", scheduled.id),
+            None => "Scheduling for".to_string(),
+        },
+        channel_id.mention(),
+        send_at,
+        send_at,
+        match cron {
+            Some(cron) => format!(", then repeating on `{}`", cron),
+            None => String::new(),
+        }
+    );
+
+    show_preview(ctx, interaction, id, draft, description, mentions).await
+}
+
+/// Follows up with `draft` as it will look, then with an ephemeral `description` of what sending
+/// the pending draft `id` does and the controls for it, starting out allowing `mentions`.
+async fn show_preview(
+    ctx: &Context,
+    interaction: &ModalSubmitInteraction,
+    id: i64,
+    draft: &MessageDraft,
+    description: String,
+    mentions: &str,
+) -> InteractionResult {
+    // Shown exactly as it will be sent, except that nobody is pinged by it
+    interaction
+        .create_followup_message(&ctx.http, |followup| {
+            if let Some(content) = &draft.content {
+                followup.content(content);
+            }
+            followup
+                .add_embeds(draft.create_embeds())
+                .set_components(draft.create_components())
+                .allowed_mentions(|mentions| mentions.empty_parse())
+        })
+        .await?;
+
+    interaction
+        .create_followup_message(&ctx.http, |followup| {
+            followup
+                .flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                .embed(|embed| {
+                    embed
+                        .title("Preview")
+                        .description(description)
+                        .footer(|footer| footer.text("Nothing is sent until you press Send."))
+                })
+                .components(|components| preview_components(components, id, mentions))
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn button_botmsg_confirm_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    pending_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    send_pending(ctx, db, interaction, pending_id, admin_role_id, false).await
+}
+
+pub async fn button_botmsg_ping_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    pending_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    send_pending(ctx, db, interaction, pending_id, admin_role_id, true).await
+}
+
+/// Sends a previewed draft, first asking for confirmation if it would ping everyone or some roles
+/// and that hasn't been confirmed yet.
+async fn send_pending(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    pending_id: &str,
+    admin_role_id: u64,
+    ping_confirmed: bool,
+) -> InteractionResult {
+    let id = parse_pending_id(&interaction, pending_id, admin_role_id)?;
+    let user_id = interaction.user.id.0;
+
+    if !ping_confirmed {
+        if let Some(pending) = PendingDraft::get(db, id, user_id)? {
+            let draft = MessageDraft::from_json(&pending.json).map_err(InteractionError::Other)?;
+
+            // Mentions added by editing a message don't notify anyone
+            if pending.message_id.is_none() {
+                let pinged = mass_mentions(&draft, &pending.mentions);
+                if !pinged.is_empty() {
+                    interaction
+                        .create_interaction_response(&ctx.http, |response| {
+                            response
+                                .kind(InteractionResponseType::UpdateMessage)
+                                .interaction_response_data(|data| {
+                                    data.content(format!(
+                                        "⚠️ This will ping {}, are you sure?",
+                                        pinged.join(", ")
+                                    ))
+                                    .allowed_mentions(|mentions| mentions.empty_parse())
+                                    .components(|components| ping_buttons(components, id))
+                                })
+                        })
+                        .await?;
+
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    let pending = match PendingDraft::take(db, id, user_id)? {
+        Some(pending) => pending,
+        None => return close_preview(&ctx, &interaction, EXPIRED).await,
+    };
+
+    let draft = MessageDraft::from_json(&pending.json).map_err(InteractionError::Other)?;
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    // The buttons are removed before sending, so the draft can't be sent twice
+    if pending.kind == PendingDraft::SCHEDULE {
+        close_preview(&ctx, &interaction, "Scheduling…").await?;
+
+        let content = schedule_pending(db, &pending, &draft, user_id)?;
+
+        interaction
+            .create_followup_message(&ctx.http, |followup| {
+                followup
+                    .flags(flags)
+                    .content(content)
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+            .await?;
+
+        return Ok(());
+    }
+
+    close_preview(&ctx, &interaction, "Sending…").await?;
+
+    let channel_id = ChannelId(pending.channel_id);
+    let msg_id = pending.message_id.map(MessageId);
+
+    let role_menu = RoleMenu::get(db, msg_id)?;
+    let result = send_draft(
+        &ctx.http,
+        db,
+        channel_id,
+        msg_id,
+        &draft,
+        role_menu.as_ref(),
+        &pending.mentions,
+    )
+    .await;
+    if let Ok(msg) = &result {
+        record_revision(db, msg, pending.name.as_deref(), user_id)?;
+        if msg_id.is_none() {
+            record_pings(db, msg, user_id)?;
+        }
+    }
+
+    interaction
+        .create_followup_message(&ctx.http, |followup| {
+            followup.flags(flags);
+            delivery_followup(followup, msg_id, result)
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Sets who a previewed draft may ping once sent.
+pub async fn select_botmsg_mentions_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    pending_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    let id = parse_pending_id(&interaction, pending_id, admin_role_id)?;
+
+    let mentions = interaction
+        .data
+        .values
+        .get(0)
+        .map(String::as_str)
+        .filter(|mentions| MENTION_OPTIONS.iter().any(|(value, _)| value == mentions))
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    if !PendingDraft::set_mentions(db, id, interaction.user.id.0, mentions)? {
+        return close_preview(&ctx, &interaction, EXPIRED).await;
+    }
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.components(|components| preview_components(components, id, mentions))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Reopens the modal the draft was written in, filled in with the draft.
+pub async fn button_botmsg_revise_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    pending_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    let id = parse_pending_id(&interaction, pending_id, admin_role_id)?;
+
+    let pending = match PendingDraft::take(db, id, interaction.user.id.0)? {
+        Some(pending) => pending,
+        None => return close_preview(&ctx, &interaction, EXPIRED).await,
+    };
+
+    let draft = MessageDraft::from_json(&pending.json).map_err(InteractionError::Other)?;
+
+    let channel_id = ChannelId(pending.channel_id);
+    let msg_id = pending.message_id.map(MessageId);
+    let name = pending.name.as_deref();
+    let content = draft.content.as_deref().unwrap_or("");
+
+    // Drafts too big for the modal can't be prefilled
+    let json = Some(pending.json.as_str()).filter(|json| json.chars().count() <= MAX_INPUT_LENGTH);
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|data| match (pending.kind.as_str(), msg_id) {
+                    (PendingDraft::EDIT, Some(msg_id)) => {
+                        edit_modal(data, channel_id, msg_id, content)
+                    }
+                    (PendingDraft::EMBED, _) => embed_modal(data, channel_id, msg_id, name, &draft),
+                    (PendingDraft::JSON, _) => json_modal(data, channel_id, msg_id, name, json),
+                    (PendingDraft::SCHEDULE, _) => schedule_modal(
+                        data,
+                        pending.scheduled_id,
+                        &pending.channel_id.to_string(),
+                        content,
+                        pending.send_at,
+                        pending.cron.as_deref(),
+                    ),
+                    _ => send_modal(data, channel_id, name, content),
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn button_botmsg_cancel_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    pending_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    let id = parse_pending_id(&interaction, pending_id, admin_role_id)?;
+
+    let content = match PendingDraft::take(db, id, interaction.user.id.0)? {
+        Some(_) => "Cancelled, nothing was sent.",
+        None => EXPIRED,
+    };
+
+    close_preview(&ctx, &interaction, content).await
+}
+
+const EXPIRED: &str = "This preview was already sent or cancelled, or has expired.";
+
+/// Checks the user may act on previews, and parses the id of the draft previewed.
+fn parse_pending_id(
+    interaction: &MessageComponentInteraction,
+    pending_id: &str,
+    admin_role_id: u64,
+) -> Result<i64, InteractionError> {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    pending_id
+        .parse::<i64>()
+        .map_err(|_| InteractionError::UnprocessableRequest)
+}
+
+/// Replaces the buttons under a preview with a note of what became of it.
+async fn close_preview(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    content: &str,
+) -> InteractionResult {
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.content(content).components(|components| components)
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn preview_components<'a>(
+    components: &'a mut CreateComponents,
+    pending_id: i64,
+    mentions: &str,
+) -> &'a mut CreateComponents {
+    components
+        .create_action_row(|action_row| {
+            action_row.create_select_menu(|menu| {
+                menu.custom_id(format!("{}:{}", ID_SELECT_BOTMSG_MENTIONS, pending_id))
+                    .min_values(1)
+                    .max_values(1)
+                    .options(|options| {
+                        for (value, label) in MENTION_OPTIONS {
+                            options.create_option(|option| {
+                                option
+                                    .label(label)
+                                    .value(value)
+                                    .default_selection(value == mentions)
+                            });
+                        }
+                        options
+                    })
+            })
+        })
+        .create_action_row(|action_row| {
+            action_row
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Success)
+                        .label("Send")
+                        .custom_id(format!("{}:{}", ID_BUTTON_BOTMSG_CONFIRM, pending_id))
+                })
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Primary)
+                        .label("Edit")
+                        .custom_id(format!("{}:{}", ID_BUTTON_BOTMSG_REVISE, pending_id))
+                })
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Danger)
+                        .label("Cancel")
+                        .custom_id(format!("{}:{}", ID_BUTTON_BOTMSG_CANCEL, pending_id))
+                })
+        })
+}
+
+fn ping_buttons(components: &mut CreateComponents, pending_id: i64) -> &mut CreateComponents {
+    components.create_action_row(|action_row| {
+        action_row
+            .create_button(|button| {
+                button
+                    .style(ButtonStyle::Danger)
+                    .label("Send and ping")
+                    .custom_id(format!("{}:{}", ID_BUTTON_BOTMSG_PING, pending_id))
+            })
+            .create_button(|button| {
+                button
+                    .style(ButtonStyle::Primary)
+                    .label("Edit")
+                    .custom_id(format!("{}:{}", ID_BUTTON_BOTMSG_REVISE, pending_id))
+            })
+            .create_button(|button| {
+                button
+                    .style(ButtonStyle::Secondary)
+                    .label("Cancel")
+                    .custom_id(format!("{}:{}", ID_BUTTON_BOTMSG_CANCEL, pending_id))
+            })
+    })
+}
+
+/// Describes how a message changes from `old` to `new`, as diffs of its content and of the JSON of
+/// the rest of it.
+fn changes(old: &MessageDraft, new: &MessageDraft) -> String {
+    let mut sections = Vec::new();
+
+    let old_content = old.content.as_deref().unwrap_or("");
+    let new_content = new.content.as_deref().unwrap_or("");
+    if old_content != new_content {
+        sections.push(format!("**Content**\n{}", diff(old_content, new_content)));
+    }
+
+    let rest = |draft: &MessageDraft| {
+        MessageDraft {
+            content: None,
+            ..draft.clone()
+        }
+        .to_json()
+    };
+    let (old_rest, new_rest) = (rest(old), rest(new));
+    if old_rest != new_rest {
+        sections.push(format!(
+            "**Embeds and buttons**\n{}",
+            diff(&old_rest, &new_rest)
+        ));
+    }
+
+    if sections.is_empty() {
+        "Nothing changed.".to_string()
+    } else {
+        sections.join("\n")
+    }
+}
+
+/// A diff code block of the lines changed from `old` to `new`, with a little context.
+fn diff(old: &str, new: &str) -> String {
+    let lines = diff_lines(old, new);
+    let near_change = |k: usize| {
+        lines[k.saturating_sub(DIFF_CONTEXT)..(k + DIFF_CONTEXT + 1).min(lines.len())]
+            .iter()
+            .any(|(mark, _)| *mark != ' ')
+    };
+
+    let mut shown = String::new();
+    let mut skipped = false;
+    for (k, (mark, line)) in lines.iter().enumerate() {
+        if !near_change(k) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            shown.push_str("…\n");
+            skipped = false;
+        }
+        // Keeps lines of the message from closing the code block early
+        shown.push_str(&format!(
+            "{} {}\n",
+            mark,
+            line.replace("```", "`\u{200b}``")
+        ));
+    }
+    if skipped {
+        shown.push_str("…\n");
+    }
+
+    format!(
+        "```diff\n{}\n```",
+        truncate(shown.trim_end(), MAX_DIFF_LENGTH)
+    )
+}
+
+/// Pairs each line of `old` and `new` with `-` if it was removed, `+` if it was added or ` ` if it
+/// is in both, going by their longest common subsequence.
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    if old.len() * new.len() > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|line| ('-', *line))
+            .chain(new.iter().map(|line| ('+', *line)))
+            .collect();
+    }
+
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for (i, old_line) in old.iter().enumerate().rev() {
+        for (j, new_line) in new.iter().enumerate().rev() {
+            common[i][j] = if old_line == new_line {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| ('-', *line)));
+    lines.extend(new[j..].iter().map(|line| ('+', *line)));
+
+    lines
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use serenity::{
+    client::Context,
+    model::interactions::{
+        application_command::ApplicationCommandInteraction, InteractionResponseType,
+    },
+};
+
+use crate::{
+    db::{
+        models::{Challenge, Score, Solve},
+        Database,
+    },
+    get_user_option, truncate, InteractionError, InteractionResult,
+};
+
+/// Name of the user context-menu command, which is shown as-is in Discord.
+pub const CMD_VIEW_PROFILE: &str = "View CTF profile";
+
+/// How many of the most recent solves are shown in the timeline.
+const TIMELINE_LENGTH: usize = 10;
+
+/// Shows a player's profile, for either `/profile [user]` or the user context-menu command.
+pub async fn cmd_profile(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    // Context-menu commands target a user, slash commands take an optional user option
+    let user_id = match command.data.target_id {
+        Some(target_id) => target_id.to_user_id(),
+        None => get_user_option(&command.data.options, "user").unwrap_or(command.user.id),
+    };
+
+    // The caller isn't among the resolved users when they leave out the user option
+    let user = if user_id == command.user.id {
+        command.user.clone()
+    } else {
+        command
+            .data
+            .resolved
+            .users
+            .get(&user_id)
+            .cloned()
+            .ok_or(InteractionError::UnprocessableRequest)?
+    };
+
+    let scores = Score::get_scoreboard(db)?;
+    let (rank, points) = match scores.iter().position(|s| s.user_id == user.id.0) {
+        Some(rank) => (format!("#{}", rank + 1), scores[rank].points),
+        None => ("Unranked".to_string(), 0),
+    };
+
+    let solves = Solve::get_by_user(db, user.id.0)?;
+    let first_bloods = Solve::get_first_bloods(db, user.id.0)?;
+
+    let challenges: Vec<(Solve, Challenge)> = solves
+        .into_iter()
+        .filter_map(|solve| match Challenge::get_by_id(db, solve.challenge_id) {
+            Ok(Some(challenge)) => Some(Ok((solve, challenge))),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut by_category: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (_, challenge) in &challenges {
+        by_category
+            .entry(&challenge.category)
+            .or_default()
+            .push(&challenge.name);
+    }
+
+    let solved = if by_category.is_empty() {
+        "Nothing yet!".to_string()
+    } else {
+        by_category
+            .iter()
+            .map(|(category, names)| format!("**{}**: {}", category, names.join(", ")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let first_blood_names = challenges
+        .iter()
+        .filter(|(_, c)| first_bloods.contains(&c.id))
+        .map(|(_, c)| c.name.as_str())
+        .collect::<Vec<_>>();
+
+    let timeline = challenges
+        .iter()
+        .rev()
+        .take(TIMELINE_LENGTH)
+        .map(|(solve, challenge)| format!("<t:{}:f> **{}**", solve.solved_at, challenge.name))
+        .collect::<Vec<_>>();
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.embed(|embed| {
+                        embed
+                            .title(format!("{}'s profile", user.name))
+                            .colour((0, 128, 255))
+                            .thumbnail(user.face())
+                            .field("Score", points, true)
+                            .field("Rank", rank, true)
+                            .field("Solves", challenges.len(), true)
+                            .field("Solved challenges", truncate(&solved, 1024), false);
+
+                        if !first_blood_names.is_empty() {
+                            embed.field(
+                                "🩸 First bloods",
+                                truncate(&first_blood_names.join(", "), 1024),
+                                false,
+                            );
+                        }
+
+                        if !timeline.is_empty() {
+                            embed.field(
+                                "Recent solves",
+                                truncate(&timeline.join("\n"), 1024),
+                                false,
+                            );
+                        }
+
+                        embed
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
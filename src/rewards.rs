@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use serenity::{
+    client::Context,
+    model::{
+        guild::Member,
+        id::{GuildId, RoleId, UserId},
+        interactions::{
+            application_command::ApplicationCommandInteraction, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{Challenge, RoleReward, Sanction, Score, Setting, Solve},
+        Database,
+    },
+    get_option, response_flags, truncate, InteractionError, InteractionResult,
+};
+
+/// Discord returns at most this many members per request.
+const MEMBERS_PER_REQUEST: u64 = 1000;
+
+pub async fn cmd_rolereward(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    match command.data.options.get(0).map(|o| o.name.as_str()) {
+        Some("add") => cmd_rolereward_add(ctx, db, command).await,
+        Some("list") => cmd_rolereward_list(ctx, db, command).await,
+        Some("remove") => cmd_rolereward_remove(ctx, db, command).await,
+        Some("reconcile") => cmd_rolereward_reconcile(ctx, db, command).await,
+        _ => Err(InteractionError::UnprocessableRequest),
+    }
+}
+
+async fn cmd_rolereward_add(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let role_id = get_option(options, "role")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let challenge_id = get_option(options, "challenge")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64());
+
+    let category = get_option(options, "category")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str());
+
+    let score = get_option(options, "score")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64());
+
+    let requirement = match (challenge_id, category, score) {
+        (Some(challenge_id), None, None) => Challenge::get_by_id(db, challenge_id)?
+            .map(|challenge| format!("solving **{}**", challenge.name)),
+        (None, Some(category), None) => {
+            Some(format!("solving every challenge in **{}**", category))
+        }
+        (None, None, Some(score)) => Some(format!("reaching **{}** points", score)),
+        _ => None,
+    };
+
+    let description = match requirement {
+        Some(requirement) => {
+            let id = RoleReward::create(db, role_id, challenge_id, category, score)?;
+
+            format!(
+                "`{}` {} is now granted for {}.\nRun `/rolereward reconcile` to grant it to \
+                existing players.",
+                id,
+                RoleId(role_id).mention(),
+                requirement
+            )
+        }
+        None => "Choose exactly one of a challenge, category or score!".to_string(),
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags)
+                        .embed(|embed| embed.title("Add role reward").description(description))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn cmd_rolereward_list(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let rewards = RoleReward::get_all(db)?;
+
+    let description = if rewards.is_empty() {
+        "No role rewards configured.".to_string()
+    } else {
+        rewards
+            .iter()
+            .map(|reward| {
+                let requirement = match (reward.challenge_id, &reward.category, reward.score) {
+                    (Some(challenge_id), _, _) => match Challenge::get_by_id(db, challenge_id)? {
+                        Some(challenge) => format!("solving **{}**", challenge.name),
+                        None => format!("solving challenge `{}`", challenge_id),
+                    },
+                    (_, Some(category), _) => {
+                        format!("solving every challenge in **{}**", category)
+                    }
+                    (_, _, Some(score)) => format!("reaching **{}** points", score),
+                    _ => "nothing".to_string(),
+                };
+
+                Ok(format!(
+                    "`{}` {} for {}",
+                    reward.id,
+                    RoleId(reward.role_id).mention(),
+                    requirement
+                ))
+            })
+            .collect::<Result<Vec<_>, InteractionError>>()?
+            .join("\n")
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Role rewards")
+                            .description(truncate(&description, 4096))
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn cmd_rolereward_remove(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "id"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let title = if RoleReward::delete(db, id)? {
+        "Removed role reward successfully!"
+    } else {
+        "No role reward with that id!"
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed.title(title).description(
+                            "Players keep the role until `/rolereward reconcile` is run.",
+                        )
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Re-syncs the reward roles of every member of the guild, e.g. after the bot was down.
+async fn cmd_rolereward_reconcile(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let guild_id = command
+        .guild_id
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    // Going through every member takes a while
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags))
+        })
+        .await?;
+
+    let rewards = Rewards::load(db)?;
+
+    let (mut checked, mut granted, mut removed) = (0, 0, 0);
+    let mut after = None;
+    loop {
+        // Listing members needs the server members intent to be enabled for the bot
+        let mut members = guild_id
+            .members(&ctx.http, Some(MEMBERS_PER_REQUEST), after)
+            .await?;
+
+        for member in &mut members {
+            if member.user.bot {
+                continue;
+            }
+
+            let (member_granted, member_removed) = rewards.sync(&ctx, db, member).await?;
+            checked += 1;
+            granted += member_granted;
+            removed += member_removed;
+        }
+
+        match members.last() {
+            Some(member) if members.len() as u64 == MEMBERS_PER_REQUEST => {
+                after = Some(member.user.id)
+            }
+            _ => break,
+        }
+    }
+
+    command
+        .create_followup_message(&ctx.http, |message| {
+            message.embed(|embed| {
+                embed
+                    .title("Reconciled role rewards")
+                    .field("Members checked", checked, true)
+                    .field("Roles granted", granted, true)
+                    .field("Roles removed", removed, true)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Grants `user_id` the reward roles they have earned and removes the ones they no longer have,
+/// e.g. after a solve or a revoked solve.
+pub async fn sync_roles(
+    ctx: &Context,
+    db: &Database,
+    guild_id: Option<GuildId>,
+    user_id: UserId,
+) -> InteractionResult {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    let rewards = Rewards::load(db)?;
+
+    if rewards.rewards.is_empty() {
+        return Ok(());
+    }
+
+    let mut member = guild_id.member(ctx, user_id).await?;
+    rewards.sync(ctx, db, &mut member).await?;
+
+    Ok(())
+}
+
+/// Everything needed to work out which reward roles users have earned, loaded once so many users
+/// can be checked at a time.
+struct Rewards {
+    rewards: Vec<RoleReward>,
+    challenges: Vec<Challenge>,
+    scores: HashMap<u64, i64>,
+}
+
+impl Rewards {
+    fn load(db: &Database) -> Result<Self, InteractionError> {
+        Ok(Self {
+            rewards: RoleReward::get_all(db)?,
+            challenges: Challenge::get_all(db)?,
+            scores: Score::get_scoreboard(db)?
+                .into_iter()
+                .map(|score| (score.user_id, score.points))
+                .collect(),
+        })
+    }
+
+    /// Finds the reward roles `user_id` has earned, or `None` if theirs shouldn't change.
+    ///
+    /// Disqualified users earn none, while shadow banned users keep whatever they had, as losing
+    /// their roles would tell them they were banned.
+    fn earned(&self, db: &Database, user_id: u64) -> Result<Option<Vec<RoleId>>, InteractionError> {
+        match Sanction::get_by_user(db, user_id)?.map(|sanction| sanction.kind) {
+            Some(kind) if kind == Sanction::SHADOW_BAN => return Ok(None),
+            Some(_) => return Ok(Some(Vec::new())),
+            None => {}
+        }
+
+        let solved: Vec<i64> = Solve::get_by_user(db, user_id)?
+            .iter()
+            .map(|s| s.challenge_id)
+            .collect();
+
+        let score = self.scores.get(&user_id).copied().unwrap_or(0);
+
+        Ok(Some(
+            self.rewards
+                .iter()
+                .filter(
+                    |reward| match (reward.challenge_id, &reward.category, reward.score) {
+                        (Some(challenge_id), _, _) => solved.contains(&challenge_id),
+                        (_, Some(category), _) => {
+                            let mut in_category = self
+                                .challenges
+                                .iter()
+                                .filter(|c| &c.category == category)
+                                .peekable();
+
+                            in_category.peek().is_some()
+                                && in_category.all(|c| solved.contains(&c.id))
+                        }
+                        (_, _, Some(threshold)) => score >= threshold,
+                        _ => false,
+                    },
+                )
+                .map(|reward| RoleId(reward.role_id))
+                .collect(),
+        ))
+    }
+
+    /// Adds and removes reward roles of `member` to match what they have earned, returning how
+    /// many were granted and removed.
+    ///
+    /// Roles used as rewards are managed entirely by the bot, so they are removed from members who
+    /// haven't earned them even if they were given out by hand.
+    async fn sync(
+        &self,
+        ctx: &Context,
+        db: &Database,
+        member: &mut Member,
+    ) -> Result<(usize, usize), InteractionError> {
+        let earned = match self.earned(db, member.user.id.0)? {
+            Some(earned) => earned,
+            None => return Ok((0, 0)),
+        };
+
+        let mut role_ids: Vec<RoleId> = self
+            .rewards
+            .iter()
+            .map(|reward| RoleId(reward.role_id))
+            .collect();
+        role_ids.sort();
+        role_ids.dedup();
+
+        let (mut granted, mut removed) = (0, 0);
+        for role_id in role_ids {
+            match (earned.contains(&role_id), member.roles.contains(&role_id)) {
+                (true, false) => {
+                    member.add_role(&ctx.http, role_id).await?;
+                    granted += 1;
+                }
+                (false, true) => {
+                    member.remove_role(&ctx.http, role_id).await?;
+                    removed += 1;
+                }
+                _ => {}
+            }
+        }
+
+        Ok((granted, removed))
+    }
+}
@@ -0,0 +1,203 @@
+use serenity::{
+    client::Context,
+    model::{
+        id::{RoleId, UserId},
+        interactions::{
+            application_command::ApplicationCommandInteraction,
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{AuditLog, Sanction},
+        Database,
+    },
+    get_option, get_user_option, rewards, truncate, InteractionError, InteractionResult,
+};
+
+pub async fn cmd_sanction(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    match command.data.options.get(0).map(|o| o.name.as_str()) {
+        Some("disqualify") => cmd_sanction_set(ctx, db, command, Sanction::DISQUALIFY).await,
+        Some("shadowban") => cmd_sanction_set(ctx, db, command, Sanction::SHADOW_BAN).await,
+        Some("lift") => cmd_sanction_lift(ctx, db, command).await,
+        Some("list") => cmd_sanction_list(ctx, db, command).await,
+        _ => Err(InteractionError::UnprocessableRequest),
+    }
+}
+
+/// Describes a kind of sanction for admins.
+fn describe_kind(kind: &str) -> &str {
+    match kind {
+        Sanction::DISQUALIFY => "Disqualified",
+        Sanction::SHADOW_BAN => "Shadow banned",
+        kind => kind,
+    }
+}
+
+async fn cmd_sanction_set(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    kind: &str,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let user_id = get_user_option(options, "user").ok_or(InteractionError::UnprocessableRequest)?;
+
+    let reason = get_option(options, "reason")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let old_kind = Sanction::get_by_user(db, user_id.0)?.map(|s| s.kind);
+
+    Sanction::set(db, user_id.0, kind, reason, command.user.id.0)?;
+    AuditLog::record(
+        db,
+        command.user.id.0,
+        AuditLog::USER_SANCTION,
+        user_id.0 as i64,
+        old_kind.as_deref(),
+        Some(kind),
+    )?;
+
+    // Shadow bans only work if the user doesn't find out, so keep all of this ephemeral
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| {
+                            embed
+                                .title("Sanctioned user successfully!")
+                                .description(format!(
+                                    "{} is now **{}** and hidden from the scoreboard.",
+                                    user_id.mention(),
+                                    describe_kind(kind).to_lowercase()
+                                ))
+                                .field("Reason", truncate(reason, 1024), false)
+                        })
+                })
+        })
+        .await?;
+
+    // Shadow banned users keep their reward roles, see `rewards`
+    if kind == Sanction::DISQUALIFY {
+        rewards::sync_roles(&ctx, db, command.guild_id, user_id).await?;
+    }
+
+    Ok(())
+}
+
+async fn cmd_sanction_lift(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let user_id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_user_option(&o.options, "user"))
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let old_kind = Sanction::get_by_user(db, user_id.0)?.map(|s| s.kind);
+
+    let description = if Sanction::lift(db, user_id.0)? {
+        AuditLog::record(
+            db,
+            command.user.id.0,
+            AuditLog::USER_SANCTION_LIFT,
+            user_id.0 as i64,
+            old_kind.as_deref(),
+            None,
+        )?;
+
+        format!("{} is no longer sanctioned.", user_id.mention())
+    } else {
+        format!("{} is not sanctioned!", user_id.mention())
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| embed.title("Lift sanction").description(description))
+                })
+        })
+        .await?;
+
+    rewards::sync_roles(&ctx, db, command.guild_id, user_id).await?;
+
+    Ok(())
+}
+
+async fn cmd_sanction_list(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let sanctions = Sanction::get_all(db)?;
+
+    let description = if sanctions.is_empty() {
+        "No users are sanctioned.".to_string()
+    } else {
+        sanctions
+            .iter()
+            .map(|sanction| {
+                format!(
+                    "{} **{}** <t:{}:R> by {}: {}",
+                    UserId(sanction.user_id).mention(),
+                    describe_kind(&sanction.kind),
+                    sanction.created_at,
+                    UserId(sanction.actor_id).mention(),
+                    sanction.reason
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| {
+                            embed
+                                .title("Sanctions")
+                                .description(truncate(&description, 4096))
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,880 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serenity::{
+    builder::CreateInteractionResponseData,
+    client::Context,
+    http::{Http, HttpError},
+    model::{
+        id::{ChannelId, RoleId, UserId},
+        interactions::{
+            application_command::ApplicationCommandInteraction, message_component::InputTextStyle,
+            modal::ModalSubmitInteraction, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{PendingDraft, ScheduledMessage, Setting},
+        Database,
+    },
+    drafts::MessageDraft,
+    get_input_value, get_option,
+    messages::{defer_preview, record_pings, send_draft},
+    previews, response_flags, truncate, InteractionError, InteractionResult,
+};
+
+/// Optionally followed by `:<scheduled message id>` when editing an existing schedule.
+pub const ID_MODAL_BOTMSG_SCHEDULE: &str = "modal_botmsg_schedule";
+
+pub const ID_INPUT_CONTENT_MODAL_BOTMSG_SCHEDULE: &str = "input_content_modal_botmsg_schedule";
+pub const ID_INPUT_CHAN_MODAL_BOTMSG_SCHEDULE: &str = "input_chan_modal_botmsg_schedule";
+pub const ID_INPUT_AT_MODAL_BOTMSG_SCHEDULE: &str = "input_at_modal_botmsg_schedule";
+pub const ID_INPUT_CRON_MODAL_BOTMSG_SCHEDULE: &str = "input_cron_modal_botmsg_schedule";
+
+/// How often the delivery task checks for messages that are due.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How far ahead to look for the next run of a cron schedule before giving up, long enough to
+/// find e.g. the 29th of February even across a skipped leap year.
+const CRON_SEARCH_DAYS: i64 = 366 * 8;
+
+/// How long to wait before retrying a one-off message that couldn't be sent, in seconds.
+const RETRY_DELAY: i64 = 5 * 60;
+
+/// Opens the modal for scheduling a new message in a channel.
+pub async fn cmd_botmsg_schedule(
+    ctx: Context,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let channel_id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "channel"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|data| {
+                    schedule_modal(data, None, channel_id, "", None, None)
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Opens the scheduling modal filled in with an existing scheduled message.
+pub async fn cmd_botmsg_reschedule(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "id"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let scheduled = match ScheduledMessage::get_by_id(db, id)? {
+        Some(scheduled) => scheduled,
+        None => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(flags)
+                                .content("No scheduled message with that id!")
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|data| {
+                    schedule_modal(
+                        data,
+                        Some(scheduled.id),
+                        &scheduled.channel_id.to_string(),
+                        &scheduled.content,
+                        Some(scheduled.send_at),
+                        scheduled.cron.as_deref(),
+                    )
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Fills in the modal for scheduling `content` to be sent to `channel_id`, or for rescheduling the
+/// scheduled message `scheduled_id` if given.
+pub fn schedule_modal<'a, 'b>(
+    data: &'b mut CreateInteractionResponseData<'a>,
+    scheduled_id: Option<i64>,
+    channel_id: &str,
+    content: &str,
+    send_at: Option<i64>,
+    cron: Option<&str>,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    match scheduled_id {
+        Some(id) => data
+            .custom_id(format!("{}:{}", ID_MODAL_BOTMSG_SCHEDULE, id))
+            .title(format!("Edit scheduled message #{}", id)),
+        None => data
+            .custom_id(ID_MODAL_BOTMSG_SCHEDULE)
+            .title("Schedule message as bot"),
+    };
+
+    data.components(|components| {
+        components
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_CONTENT_MODAL_BOTMSG_SCHEDULE)
+                        .label("Message content")
+                        .max_length(2000)
+                        .value(content)
+                        .required(true)
+                        .style(InputTextStyle::Paragraph)
+                })
+            })
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_CHAN_MODAL_BOTMSG_SCHEDULE)
+                        .label("Channel Id")
+                        .value(channel_id)
+                        .max_length(20)
+                        .required(true)
+                        .style(InputTextStyle::Short)
+                })
+            })
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_AT_MODAL_BOTMSG_SCHEDULE)
+                        .label("Send at (YYYY-MM-DD HH:MM UTC, +30m, +2h)")
+                        .max_length(20)
+                        .required(false)
+                        .style(InputTextStyle::Short);
+                    match send_at {
+                        Some(send_at) => input_text.value(format_time(send_at)),
+                        None => input_text.placeholder("Leave empty to follow the repeat schedule"),
+                    }
+                })
+            })
+            .create_action_row(|action_row| {
+                action_row.create_input_text(|input_text| {
+                    input_text
+                        .custom_id(ID_INPUT_CRON_MODAL_BOTMSG_SCHEDULE)
+                        .label("Repeat (cron, UTC)")
+                        .max_length(100)
+                        .required(false)
+                        .style(InputTextStyle::Short);
+                    match cron {
+                        Some(cron) => input_text.value(cron),
+                        None => input_text.placeholder("e.g. 0 12 * * 1 for every Monday noon"),
+                    }
+                })
+            })
+    })
+}
+
+/// Lists every pending scheduled message.
+pub async fn cmd_botmsg_schedules(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let scheduled = ScheduledMessage::get_all(db)?;
+
+    let description = if scheduled.is_empty() {
+        "There are no scheduled messages.".to_string()
+    } else {
+        scheduled
+            .iter()
+            .map(|scheduled| {
+                format!(
+                    "`#{}` {} by {} <t:{}:R>{}: {}",
+                    scheduled.id,
+                    ChannelId(scheduled.channel_id).mention(),
+                    UserId(scheduled.created_by).mention(),
+                    scheduled.send_at,
+                    match &scheduled.cron {
+                        Some(cron) => format!(", repeating `{}`", cron),
+                        None => String::new(),
+                    },
+                    truncate(&scheduled.content, 50)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Scheduled messages")
+                            .description(truncate(&description, 4096))
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn cmd_botmsg_unschedule(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "id"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let content = if ScheduledMessage::delete(db, id)? {
+        "Cancelled scheduled message!"
+    } else {
+        "No scheduled message with that id!"
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags).content(content))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Previews a message to schedule, or to replace the scheduled message with id `scheduled_id` if
+/// given. It is only scheduled once confirmed, see [`schedule_pending`].
+pub async fn modal_botmsg_schedule_response(
+    ctx: Context,
+    db: &Database,
+    interaction: ModalSubmitInteraction,
+    scheduled_id: Option<&str>,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let scheduled = match scheduled_id {
+        Some(scheduled_id) => Some(ScheduledMessage::get_by_id(
+            db,
+            scheduled_id
+                .parse::<i64>()
+                .map_err(|_| InteractionError::UnprocessableRequest)?,
+        )?),
+        None => None,
+    };
+
+    let rows = &interaction.data.components;
+    let content = get_input_value(rows, 0).ok_or(InteractionError::UnprocessableRequest)?;
+    let channel_id = get_input_value(rows, 1).and_then(|v| v.trim().parse::<u64>().ok());
+    let send_at = get_input_value(rows, 2).unwrap_or("").trim();
+    let cron = get_input_value(rows, 3)
+        .map(str::trim)
+        .filter(|cron| !cron.is_empty());
+
+    let now = now();
+
+    let outcome = match (channel_id, &scheduled) {
+        (_, Some(None)) => Err("No scheduled message with that id!".to_string()),
+        (None, _) => Err("Invalid channel id!".to_string()),
+        (Some(channel_id), _) => match (send_at, cron.map(|cron| (cron, Cron::parse(cron)))) {
+            (_, Some((cron, None))) => Err(format!("Invalid cron expression `{}`!", cron)),
+            ("", None) => Err("Give a time to send at, a repeat schedule, or both!".to_string()),
+            ("", Some((_, Some(schedule)))) => schedule
+                .next_after(now)
+                .ok_or_else(|| "That repeat schedule never runs!".to_string())
+                .map(|send_at| (channel_id, send_at)),
+            (send_at, _) => match parse_time(send_at, now) {
+                Some(send_at) if send_at > now => Ok((channel_id, send_at)),
+                Some(_) => Err("That time is in the past!".to_string()),
+                None => Err(format!("Invalid time `{}`!", send_at)),
+            },
+        },
+    };
+
+    let (channel_id, send_at) = match outcome {
+        Ok(outcome) => outcome,
+        Err(why) => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            interaction
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(flags)
+                                .content(why)
+                                .allowed_mentions(|mentions| mentions.empty_parse())
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    defer_preview(&ctx, &interaction).await?;
+
+    let draft = MessageDraft {
+        content: Some(content.to_string()),
+        ..Default::default()
+    };
+
+    previews::preview_schedule(
+        &ctx,
+        db,
+        &interaction,
+        ChannelId(channel_id),
+        (send_at, cron, scheduled.flatten().as_ref()),
+        &draft,
+    )
+    .await
+}
+
+/// Schedules a previewed draft once confirmed by `user_id`, or reschedules the scheduled message it
+/// was previewed for, describing the outcome.
+pub fn schedule_pending(
+    db: &Database,
+    pending: &PendingDraft,
+    draft: &MessageDraft,
+    user_id: u64,
+) -> Result<String, InteractionError> {
+    let send_at = pending
+        .send_at
+        .ok_or(InteractionError::UnprocessableRequest)?;
+    let content = draft.content.as_deref().unwrap_or("");
+    let when = (send_at, pending.cron.as_deref());
+
+    let id = match pending.scheduled_id {
+        Some(id) => {
+            if ScheduledMessage::update(
+                db,
+                id,
+                pending.channel_id,
+                content,
+                &pending.mentions,
+                when,
+                user_id,
+            )? {
+                Some(id)
+            } else {
+                None
+            }
+        }
+        None => Some(ScheduledMessage::create(
+            db,
+            pending.channel_id,
+            content,
+            &pending.mentions,
+            when,
+            user_id,
+        )?),
+    };
+
+    Ok(match id {
+        Some(id) => format!(
+            "Scheduled message `#{}` will be sent in {} <t:{}:R>{}.",
+            id,
+            ChannelId(pending.channel_id).mention(),
+            send_at,
+            match &pending.cron {
+                Some(cron) => format!(" and then repeat on `{}`", cron),
+                None => String::new(),
+            }
+        ),
+        None => "No scheduled message with that id!".to_string(),
+    })
+}
+
+/// Sends scheduled messages as they fall due, forever.
+///
+/// Messages that came due while the bot was down are sent as soon as it is back, but a repeating
+/// message is only sent once for all the runs it missed.
+pub async fn deliver_scheduled_messages(http: Arc<Http>, db: Arc<Database>) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let due = match ScheduledMessage::get_due(&db) {
+            Ok(due) => due,
+            Err(why) => {
+                println!("Error when fetching scheduled messages: {:?}", why);
+                continue;
+            }
+        };
+
+        for scheduled in due {
+            let draft = MessageDraft {
+                content: Some(scheduled.content.clone()),
+                ..Default::default()
+            };
+
+            // Who the message may ping was chosen, and any mass ping confirmed, when it was last
+            // scheduled
+            let result = send_draft(
+                &http,
+                &db,
+                ChannelId(scheduled.channel_id),
+                None,
+                &draft,
+                None,
+                &scheduled.mentions,
+            )
+            .await;
+
+            // Repeating messages move on to their next run either way, and are done with once they
+            // can't run again. One-off messages are done with once sent, and retried until then
+            // unless retrying can't help
+            let next = match (scheduled.cron.as_deref(), &result) {
+                (Some(cron), _) => {
+                    Cron::parse(cron).and_then(|schedule| schedule.next_after(now()))
+                }
+                (None, Err(why)) if !is_permanent(why) => Some(now() + RETRY_DELAY),
+                (None, _) => None,
+            };
+
+            match result {
+                Ok(msg) => {
+                    if let Err(why) = record_pings(&db, &msg, scheduled.edited_by) {
+                        println!(
+                            "Error when recording pings of scheduled message #{}: {:?}",
+                            scheduled.id, why
+                        );
+                    }
+                }
+                Err(why) => {
+                    println!(
+                        "Error when sending scheduled message #{}: {:?}",
+                        scheduled.id, why
+                    );
+
+                    if let Err(why) = report_failure(&http, &db, &scheduled, &why, next).await {
+                        println!(
+                            "Error when reporting failure of scheduled message #{}: {:?}",
+                            scheduled.id, why
+                        );
+                    }
+                }
+            }
+
+            if let Err(why) = match next {
+                Some(send_at) => ScheduledMessage::set_send_at(&db, scheduled.id, send_at),
+                None => ScheduledMessage::delete(&db, scheduled.id).map(|_| ()),
+            } {
+                println!(
+                    "Error when updating scheduled message #{}: {:?}",
+                    scheduled.id, why
+                );
+            }
+        }
+    }
+}
+
+/// Whether Discord refused a message in a way that sending it again won't change, e.g. because the
+/// channel was deleted, isn't one messages can be sent in or the bot may not send messages in it.
+fn is_permanent(why: &serenity::Error) -> bool {
+    match why {
+        serenity::Error::Http(why) => matches!(
+            why.as_ref(),
+            HttpError::UnsuccessfulRequest(response)
+                if response.status_code.is_client_error()
+                    && response.status_code.as_u16() != 429
+        ),
+        _ => false,
+    }
+}
+
+/// Tells the admin channel, if there is one, that a scheduled message couldn't be sent and when it
+/// will next be tried.
+async fn report_failure(
+    http: &Http,
+    db: &Database,
+    scheduled: &ScheduledMessage,
+    why: &serenity::Error,
+    next: Option<i64>,
+) -> InteractionResult {
+    let channel_id = match Setting::get_id(db, Setting::ADMIN_CHANNEL)? {
+        Some(channel_id) => channel_id,
+        None => return Ok(()),
+    };
+
+    let next = match (next, &scheduled.cron) {
+        (Some(next), None) => format!(
+            "It will be retried <t:{}:R>, unless it is unscheduled with `/botmsg unschedule`.",
+            next
+        ),
+        (Some(next), Some(_)) => format!("It will next be sent <t:{}:R>.", next),
+        (None, _) => "It won't be sent again.".to_string(),
+    };
+
+    ChannelId(channel_id)
+        .send_message(http, |message| {
+            message.embed(|embed| {
+                embed
+                    .title("Scheduled message not sent")
+                    .colour((255, 165, 0))
+                    .description(format!(
+                        "Scheduled message `#{}` couldn't be sent to {}: {}\n\n{}",
+                        scheduled.id,
+                        ChannelId(scheduled.channel_id).mention(),
+                        why,
+                        next
+                    ))
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// The current time as a unix timestamp.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Parses a time given as `YYYY-MM-DD HH:MM` in UTC, a unix timestamp, or relative to `now` like
+/// `+30m`, `+2h` or `+1d`.
+pub fn parse_time(s: &str, now: i64) -> Option<i64> {
+    if let Some(relative) = s.strip_prefix('+') {
+        let (amount, unit) = [("m", 60), ("h", 60 * 60), ("d", 24 * 60 * 60)]
+            .into_iter()
+            .find_map(|(suffix, unit)| Some((relative.strip_suffix(suffix)?, unit)))?;
+        let amount = amount.parse::<i64>().ok()?;
+        return now.checked_add(amount.checked_mul(unit)?);
+    }
+
+    if let Ok(timestamp) = s.parse::<i64>() {
+        return Some(timestamp);
+    }
+
+    let (date, time) = s.split_once(' ')?;
+    let mut date = date.splitn(3, '-').map(|v| v.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let (hour, minute) = time.split_once(':')?;
+    let (hour, minute) = (hour.parse::<i64>().ok()?, minute.parse::<i64>().ok()?);
+
+    if !(1..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(0..24).contains(&hour)
+        || !(0..60).contains(&minute)
+    {
+        return None;
+    }
+
+    // Rejects days that don't exist in the month, e.g. the 31st of April
+    let days = days_from_civil(year, month, day);
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+
+    Some(days * 86400 + hour * 3600 + minute * 60)
+}
+
+/// Formats a unix timestamp the way [`parse_time`] reads it.
+fn format_time(time: i64) -> String {
+    let (year, month, day) = civil_from_days(time.div_euclid(86400));
+    let seconds = time.rem_euclid(86400);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+/// Days since the unix epoch of a date in the proleptic Gregorian calendar.
+// From http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date `days` days after the unix epoch, as `(year, month, day)`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+
+    (
+        year_of_era + era * 400 + if month <= 2 { 1 } else { 0 },
+        month,
+        day,
+    )
+}
+
+/// A standard five field cron expression (minute, hour, day of month, month, day of week),
+/// evaluated in UTC. Fields can be `*`, numbers, ranges like `1-5`, steps like `*/15` and lists of
+/// any of these separated by commas.
+struct Cron {
+    minutes: Vec<i64>,
+    hours: Vec<i64>,
+    days: Vec<i64>,
+    months: Vec<i64>,
+    weekdays: Vec<i64>,
+    /// Whether the day of month field was `*`, see [`Cron::matches_day`].
+    any_day: bool,
+    /// Whether the day of week field was `*`, see [`Cron::matches_day`].
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(s: &str) -> Option<Self> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return None;
+        }
+
+        // Sunday can be either 0 or 7
+        let mut weekdays: Vec<i64> = Self::parse_field(fields[4], 0, 7)?
+            .into_iter()
+            .map(|weekday| weekday % 7)
+            .collect();
+        weekdays.sort_unstable();
+        weekdays.dedup();
+
+        Some(Self {
+            minutes: Self::parse_field(fields[0], 0, 59)?,
+            hours: Self::parse_field(fields[1], 0, 23)?,
+            days: Self::parse_field(fields[2], 1, 31)?,
+            months: Self::parse_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    /// Parses a single field into the sorted values it matches, all within `min..=max`.
+    fn parse_field(field: &str, min: i64, max: i64) -> Option<Vec<i64>> {
+        let mut values = Vec::new();
+
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, step.parse::<usize>().ok().filter(|s| *s > 0)?),
+                None => (part, 1),
+            };
+
+            let (start, end) = match range.split_once('-') {
+                _ if range == "*" => (min, max),
+                Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+                // `5/15` means every 15 starting from 5
+                None if step > 1 => (range.parse().ok()?, max),
+                None => {
+                    let value = range.parse().ok()?;
+                    (value, value)
+                }
+            };
+
+            if start < min || end > max || start > end {
+                return None;
+            }
+
+            values.extend((start..=end).step_by(step));
+        }
+
+        values.sort_unstable();
+        values.dedup();
+
+        Some(values)
+    }
+
+    /// Like cron, if both the day of month and day of week are restricted then a day matching
+    /// either is enough.
+    fn matches_day(&self, day: i64, weekday: i64) -> bool {
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => self.weekdays.contains(&weekday),
+            (false, true) => self.days.contains(&day),
+            (false, false) => self.days.contains(&day) || self.weekdays.contains(&weekday),
+        }
+    }
+
+    /// Finds the first time strictly after `time` that this schedule runs at, if it ever does.
+    fn next_after(&self, time: i64) -> Option<i64> {
+        let first_day = time.div_euclid(86400);
+
+        for days in first_day..first_day + CRON_SEARCH_DAYS {
+            let (_, month, day) = civil_from_days(days);
+            // The unix epoch was a Thursday
+            let weekday = (days + 4).rem_euclid(7);
+
+            if !self.months.contains(&month) || !self.matches_day(day, weekday) {
+                continue;
+            }
+
+            for hour in &self.hours {
+                for minute in &self.minutes {
+                    let candidate = days * 86400 + hour * 3600 + minute * 60;
+                    if candidate > time {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> i64 {
+        parse_time(s, 0).unwrap()
+    }
+
+    #[test]
+    fn relative_times() {
+        assert_eq!(parse_time("+30m", 1000), Some(1000 + 30 * 60));
+        assert_eq!(parse_time("+2h", 0), Some(2 * 60 * 60));
+        assert_eq!(parse_time("+1d", 0), Some(24 * 60 * 60));
+        assert_eq!(parse_time("+", 0), None);
+        assert_eq!(parse_time("+5", 0), None);
+        assert_eq!(parse_time("+5é", 0), None);
+        assert_eq!(parse_time("+99999999999999999d", 0), None);
+    }
+
+    #[test]
+    fn absolute_times() {
+        assert_eq!(parse_time("1700000000", 0), Some(1700000000));
+        assert_eq!(time("1970-01-01 00:00"), 0);
+        assert_eq!(time("2024-02-29 12:30"), 1709209800);
+        assert_eq!(parse_time("2023-02-29 12:30", 0), None);
+        assert_eq!(parse_time("2024-04-31 00:00", 0), None);
+        assert_eq!(parse_time("2024-01-01 24:00", 0), None);
+        assert_eq!(parse_time("99999999999-01-01 00:00", 0), None);
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(days_from_civil(2000, 2, 29)), (2000, 2, 29));
+        assert_eq!(
+            civil_from_days(days_from_civil(2100, 3, 1) - 1),
+            (2100, 2, 28)
+        );
+
+        for days in -1000..100_000 {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+
+        assert_eq!(format_time(time("2024-02-29 12:30")), "2024-02-29 12:30");
+    }
+
+    #[test]
+    fn cron_fields() {
+        assert_eq!(Cron::parse_field("*/15", 0, 59), Some(vec![0, 15, 30, 45]));
+        assert_eq!(Cron::parse_field("5/15", 0, 59), Some(vec![5, 20, 35, 50]));
+        assert_eq!(
+            Cron::parse_field("1-5,3,10", 1, 31),
+            Some(vec![1, 2, 3, 4, 5, 10])
+        );
+        assert_eq!(Cron::parse_field("60", 0, 59), None);
+        assert_eq!(Cron::parse_field("5-1", 0, 59), None);
+        assert_eq!(Cron::parse_field("*/0", 0, 59), None);
+        assert_eq!(Cron::parse("0 0 * * 7").unwrap().weekdays, vec![0]);
+        assert!(Cron::parse("0 0 * *").is_none());
+    }
+
+    #[test]
+    fn cron_next_run() {
+        let daily = Cron::parse("30 12 * * *").unwrap();
+        assert_eq!(
+            daily.next_after(time("2024-01-01 12:30")),
+            Some(time("2024-01-02 12:30"))
+        );
+
+        // The 13th or any Friday, starting from Monday the 1st
+        let either = Cron::parse("0 0 13 * 5").unwrap();
+        assert_eq!(
+            either.next_after(time("2024-01-01 00:00")),
+            Some(time("2024-01-05 00:00"))
+        );
+        assert_eq!(
+            either.next_after(time("2024-01-12 00:00")),
+            Some(time("2024-01-13 00:00"))
+        );
+
+        let leap_day = Cron::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap_day.next_after(time("2024-03-01 00:00")),
+            Some(time("2028-02-29 00:00"))
+        );
+
+        let never = Cron::parse("0 0 31 2 *").unwrap();
+        assert_eq!(never.next_after(time("2024-01-01 00:00")), None);
+    }
+}
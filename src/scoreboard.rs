@@ -0,0 +1,159 @@
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    client::Context,
+    model::{
+        id::UserId,
+        interactions::{
+            application_command::ApplicationCommandInteraction,
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{models::Score, Database},
+    get_option, InteractionError, InteractionResult,
+};
+
+/// Followed by `:<page>`
+pub const ID_BUTTON_SCOREBOARD_PAGE: &str = "button_scoreboard_page";
+
+const SCORES_PER_PAGE: usize = 10;
+
+pub async fn cmd_scoreboard(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let show_me = get_option(&command.data.options, "me")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let scores = Score::get_scoreboard(db)?;
+
+    // Jump to the page the caller is on, and point them out
+    let (page, highlight) = match scores.iter().position(|s| s.user_id == command.user.id.0) {
+        Some(rank) if show_me => (rank / SCORES_PER_PAGE, Some(command.user.id.0)),
+        _ => (0, None),
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.embed(|embed| scoreboard_embed(embed, &scores, page, highlight))
+                        .components(|components| scoreboard_buttons(components, &scores, page))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn button_scoreboard_page_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    page: &str,
+) -> InteractionResult {
+    let page = page
+        .parse::<usize>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    let scores = Score::get_scoreboard(db)?;
+
+    // The scoreboard may have shrunk since the buttons were made
+    let page = page.min(page_count(&scores) - 1);
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.embed(|embed| scoreboard_embed(embed, &scores, page, None))
+                        .components(|components| scoreboard_buttons(components, &scores, page))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn page_count(scores: &[Score]) -> usize {
+    scores.len().div_ceil(SCORES_PER_PAGE).max(1)
+}
+
+fn scoreboard_embed<'a>(
+    embed: &'a mut CreateEmbed,
+    scores: &[Score],
+    page: usize,
+    highlight: Option<u64>,
+) -> &'a mut CreateEmbed {
+    let description = if scores.is_empty() {
+        "No one has scored yet!".to_string()
+    } else {
+        scores
+            .iter()
+            .enumerate()
+            .skip(page * SCORES_PER_PAGE)
+            .take(SCORES_PER_PAGE)
+            .map(|(rank, score)| {
+                let line = format!(
+                    "`#{}` {} - **{}** points{}",
+                    rank + 1,
+                    UserId(score.user_id).mention(),
+                    score.points,
+                    match score.last_solve_at {
+                        Some(last_solve_at) => format!(" (last solve <t:{}:R>)", last_solve_at),
+                        None => String::new(),
+                    }
+                );
+
+                if highlight == Some(score.user_id) {
+                    format!("__{}__", line)
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    embed
+        .title("Scoreboard")
+        .colour((255, 215, 0))
+        .description(description)
+        .footer(|footer| footer.text(format!("Page {}/{}", page + 1, page_count(scores))))
+}
+
+fn scoreboard_buttons<'a>(
+    components: &'a mut CreateComponents,
+    scores: &[Score],
+    page: usize,
+) -> &'a mut CreateComponents {
+    components.create_action_row(|action_row| {
+        action_row
+            .create_button(|button| {
+                button
+                    .style(ButtonStyle::Secondary)
+                    .label("Previous")
+                    .custom_id(format!(
+                        "{}:{}",
+                        ID_BUTTON_SCOREBOARD_PAGE,
+                        page.saturating_sub(1)
+                    ))
+                    .disabled(page == 0)
+            })
+            .create_button(|button| {
+                button
+                    .style(ButtonStyle::Secondary)
+                    .label("Next")
+                    .custom_id(format!("{}:{}", ID_BUTTON_SCOREBOARD_PAGE, page + 1))
+                    .disabled(page + 1 >= page_count(scores))
+            })
+    })
+}
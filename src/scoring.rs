@@ -0,0 +1,294 @@
+use serenity::{
+    client::Context,
+    model::{
+        id::{RoleId, UserId},
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
+            },
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{AuditLog, Challenge, ScoreAdjustment, Setting, StageSolve},
+        Database,
+    },
+    discussions, get_option, get_user_option, response_flags, rewards, truncate, InteractionError,
+    InteractionResult,
+};
+
+pub async fn cmd_score(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    match command.data.options.get(0).map(|o| o.name.as_str()) {
+        Some("award") => cmd_score_award(ctx, db, command).await,
+        Some("revoke") => cmd_score_revoke(ctx, db, command).await,
+        Some("adjust") => cmd_score_adjust(ctx, db, command).await,
+        Some("history") => cmd_score_history(ctx, db, command).await,
+        _ => Err(InteractionError::UnprocessableRequest),
+    }
+}
+
+fn get_challenge_option(
+    db: &Database,
+    options: &[ApplicationCommandInteractionDataOption],
+) -> Result<Challenge, InteractionError> {
+    let challenge_id = get_option(options, "challenge")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    Challenge::get_by_id(db, challenge_id)?.ok_or(InteractionError::UnprocessableRequest)
+}
+
+async fn cmd_score_award(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let user_id = get_user_option(options, "user").ok_or(InteractionError::UnprocessableRequest)?;
+    let challenge = get_challenge_option(db, options)?;
+
+    let awarded = StageSolve::award_challenge(db, user_id.0, challenge.id)?;
+
+    let description = if awarded {
+        AuditLog::record(
+            db,
+            command.user.id.0,
+            AuditLog::SOLVE_AWARD,
+            challenge.id,
+            None,
+            Some(&user_id.0.to_string()),
+        )?;
+
+        format!(
+            "{} has been awarded a solve for **{}**.",
+            user_id.mention(),
+            challenge.name
+        )
+    } else {
+        format!(
+            "{} has already solved **{}**.",
+            user_id.mention(),
+            challenge.name
+        )
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags)
+                        .embed(|embed| embed.title("Award solve").description(description))
+                })
+        })
+        .await?;
+
+    if awarded {
+        discussions::add_solver(&ctx, &challenge, user_id).await?;
+    }
+
+    rewards::sync_roles(&ctx, db, command.guild_id, user_id).await?;
+
+    Ok(())
+}
+
+async fn cmd_score_revoke(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let user_id = get_user_option(options, "user").ok_or(InteractionError::UnprocessableRequest)?;
+    let challenge = get_challenge_option(db, options)?;
+
+    let revoked = StageSolve::revoke_challenge(db, user_id.0, challenge.id)?;
+
+    let description = if revoked {
+        AuditLog::record(
+            db,
+            command.user.id.0,
+            AuditLog::SOLVE_REVOKE,
+            challenge.id,
+            Some(&user_id.0.to_string()),
+            None,
+        )?;
+
+        format!(
+            "{}'s progress on **{}** has been revoked.",
+            user_id.mention(),
+            challenge.name
+        )
+    } else {
+        format!(
+            "{} has no progress on **{}**.",
+            user_id.mention(),
+            challenge.name
+        )
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags)
+                        .embed(|embed| embed.title("Revoke solve").description(description))
+                })
+        })
+        .await?;
+
+    if revoked {
+        discussions::remove_solver(&ctx, &challenge, user_id).await?;
+    }
+
+    rewards::sync_roles(&ctx, db, command.guild_id, user_id).await?;
+
+    Ok(())
+}
+
+async fn cmd_score_adjust(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let user_id = get_user_option(options, "user").ok_or(InteractionError::UnprocessableRequest)?;
+
+    let points = get_option(options, "points")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let reason = get_option(options, "reason")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let id = ScoreAdjustment::create(db, user_id.0, points, reason, command.user.id.0)?;
+    AuditLog::record(
+        db,
+        command.user.id.0,
+        AuditLog::SCORE_ADJUST,
+        id,
+        None,
+        Some(&format!("{} {:+}: {}", user_id.0, points, reason)),
+    )?;
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Adjusted score successfully!")
+                            .description(format!(
+                                "{} has been given **{:+}** points.",
+                                user_id.mention(),
+                                points
+                            ))
+                            .field("Reason", truncate(reason, 1024), false)
+                    })
+                })
+        })
+        .await?;
+
+    rewards::sync_roles(&ctx, db, command.guild_id, user_id).await?;
+
+    Ok(())
+}
+
+async fn cmd_score_history(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let user_id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_user_option(&o.options, "user"))
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let adjustments = ScoreAdjustment::get_by_user(db, user_id.0)?;
+
+    let description = if adjustments.is_empty() {
+        format!("{} has no score adjustments.", user_id.mention())
+    } else {
+        adjustments
+            .iter()
+            .map(|adjustment| {
+                format!(
+                    "`{}` <t:{}:f> **{:+}** by {}: {}",
+                    adjustment.id,
+                    adjustment.created_at,
+                    adjustment.points,
+                    UserId(adjustment.actor_id).mention(),
+                    adjustment.reason
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .embed(|embed| {
+                            embed
+                                .title("Score adjustments")
+                                .description(truncate(&description, 4096))
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,750 @@
+use serenity::{
+    builder::CreateComponents,
+    client::Context,
+    http::Http,
+    model::{
+        channel::Message,
+        id::{ChannelId, MessageId, RoleId, UserId},
+        interactions::{
+            application_command::ApplicationCommandInteraction,
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        permissions::Permissions,
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{BotMessage, RoleReward, SelfRole, Setting},
+        Database,
+    },
+    drafts::MessageDraft,
+    get_option,
+    messages::{delivery_followup, send_draft, MENTIONS_NONE},
+    response_flags, truncate, InteractionError, InteractionResult,
+};
+
+/// Followed by `:<role id>`
+pub const ID_BUTTON_SELF_ROLE: &str = "button_self_role";
+/// Followed by `:<group>`
+pub const ID_SELECT_SELF_ROLE: &str = "select_self_role";
+
+/// Discord allows at most 25 buttons or select menu options on a message.
+const MAX_GROUP_SIZE: usize = 25;
+const MAX_GROUP_LENGTH: usize = 32;
+const MAX_LABEL_LENGTH: usize = 80;
+const MAX_ACTION_ROWS: usize = 5;
+const BUTTONS_PER_ROW: usize = 5;
+
+/// The roles of a group offered under a bot message, sent along with it whenever it is edited.
+pub struct RoleMenu {
+    group: String,
+    style: String,
+    exclusive: bool,
+    roles: Vec<SelfRole>,
+}
+
+impl RoleMenu {
+    pub fn new(db: &Database, group: &str, style: &str) -> Result<Self, InteractionError> {
+        Ok(Self {
+            group: group.to_string(),
+            style: style.to_string(),
+            exclusive: SelfRole::is_exclusive(db, group)?,
+            roles: SelfRole::get_by_group(db, group)?,
+        })
+    }
+
+    /// The menu under a registered bot message, if it has one.
+    pub fn for_message(
+        db: &Database,
+        message: &BotMessage,
+    ) -> Result<Option<Self>, InteractionError> {
+        match (&message.role_group, &message.role_style) {
+            (Some(group), Some(style)) => Ok(Some(Self::new(db, group, style)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// The menu under the message `msg_id`, if it is a registered bot message with one.
+    pub fn get(db: &Database, msg_id: Option<MessageId>) -> Result<Option<Self>, InteractionError> {
+        match msg_id {
+            Some(msg_id) => match BotMessage::get_by_message_id(db, msg_id.0)? {
+                Some(message) => Self::for_message(db, &message),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// How many action rows the menu takes up.
+    pub fn rows(&self) -> usize {
+        if self.roles.is_empty() {
+            0
+        } else if self.style == BotMessage::ROLES_SELECT {
+            1
+        } else {
+            self.roles.len().div_ceil(BUTTONS_PER_ROW)
+        }
+    }
+
+    /// Adds the menu's action rows after those already in `components`.
+    pub fn create_rows(&self, components: &mut CreateComponents) {
+        if self.roles.is_empty() {
+            return;
+        }
+
+        if self.style == BotMessage::ROLES_SELECT {
+            components.create_action_row(|action_row| {
+                action_row.create_select_menu(|menu| {
+                    menu.custom_id(format!("{}:{}", ID_SELECT_SELF_ROLE, self.group))
+                        .placeholder("Pick your roles")
+                        .min_values(0)
+                        .max_values(if self.exclusive {
+                            1
+                        } else {
+                            self.roles.len() as u64
+                        })
+                        .options(|options| {
+                            for role in &self.roles {
+                                options.create_option(|option| {
+                                    option.label(&role.label).value(role.role_id)
+                                });
+                            }
+                            options
+                        })
+                })
+            });
+        } else {
+            for row in self.roles.chunks(BUTTONS_PER_ROW) {
+                components.create_action_row(|action_row| {
+                    for role in row {
+                        action_row.create_button(|button| {
+                            button
+                                .style(ButtonStyle::Secondary)
+                                .label(&role.label)
+                                .custom_id(format!("{}:{}", ID_BUTTON_SELF_ROLE, role.role_id))
+                        });
+                    }
+                    action_row
+                });
+            }
+        }
+    }
+}
+
+pub async fn cmd_selfrole(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    match command.data.options.get(0).map(|o| o.name.as_str()) {
+        Some("allow") => cmd_selfrole_allow(ctx, db, command, admin_role_id).await,
+        Some("disallow") => cmd_selfrole_disallow(ctx, db, command).await,
+        Some("exclusive") => cmd_selfrole_exclusive(ctx, db, command).await,
+        Some("list") => cmd_selfrole_list(ctx, db, command).await,
+        _ => Err(InteractionError::UnprocessableRequest),
+    }
+}
+
+async fn cmd_selfrole_allow(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let role_id = get_option(options, "role")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let group = get_option(options, "group")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_lowercase())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    // Buttons are labelled with the role's name unless told otherwise
+    let label = get_option(options, "label")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_string())
+        .or_else(|| {
+            command
+                .data
+                .resolved
+                .roles
+                .get(&RoleId(role_id))
+                .map(|role| role.name.clone())
+        })
+        .unwrap_or_else(|| role_id.to_string());
+
+    let guild_id = command
+        .guild_id
+        .ok_or(InteractionError::UnprocessableRequest)?;
+    let roles = guild_id.roles(&ctx.http).await?;
+    // The @everyone role shares its id with the server
+    let everyone = roles
+        .get(&RoleId(guild_id.0))
+        .map_or_else(Permissions::empty, |role| role.permissions);
+    let role = roles
+        .get(&RoleId(role_id))
+        .ok_or(InteractionError::UnprocessableRequest)?;
+    let is_reward = RoleReward::get_all(db)?
+        .iter()
+        .any(|reward| reward.role_id == role_id);
+
+    let previous = SelfRole::get_by_id(db, role_id)?;
+
+    let others = SelfRole::get_by_group(db, &group)?
+        .into_iter()
+        .filter(|role| role.role_id != role_id)
+        .count();
+
+    // Members could otherwise give themselves powers or rewards they aren't meant to have
+    let content = if role_id == admin_role_id || role_id == guild_id.0 {
+        "Members can't be allowed to pick that role!".to_string()
+    } else if role.managed {
+        "That role is managed by Discord or an integration, so it can't be handed out!".to_string()
+    } else if !everyone.contains(role.permissions) {
+        "That role grants permissions members don't already have, so they can't be allowed to \
+        pick it!"
+            .to_string()
+    } else if is_reward {
+        "That role is a reward, so members can't be allowed to pick it!".to_string()
+    } else if group.is_empty() || group.chars().count() > MAX_GROUP_LENGTH {
+        format!(
+            "Group names must be between 1 and {} characters long!",
+            MAX_GROUP_LENGTH
+        )
+    } else if label.is_empty() || label.chars().count() > MAX_LABEL_LENGTH {
+        format!(
+            "Labels must be between 1 and {} characters long!",
+            MAX_LABEL_LENGTH
+        )
+    } else if others >= MAX_GROUP_SIZE {
+        format!(
+            "`{}` already has the most roles a message can offer, {}!",
+            group, MAX_GROUP_SIZE
+        )
+    } else {
+        SelfRole::set(db, role_id, &group, &label, command.user.id.0)?;
+
+        format!(
+            "Members can now pick {} as **{}** in group `{}`.",
+            RoleId(role_id).mention(),
+            label,
+            group
+        )
+    };
+
+    // Moving a role to another group changes what messages offering its old group show too
+    let mut groups = vec![group.as_str()];
+    if let Some(previous) = &previous {
+        if previous.role_group != group {
+            groups.push(&previous.role_group);
+        }
+    }
+
+    respond_and_refresh(&ctx, db, &command, content, &groups).await
+}
+
+async fn cmd_selfrole_disallow(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let role_id = command
+        .data
+        .options
+        .get(0)
+        .and_then(|o| get_option(&o.options, "role"))
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    match SelfRole::get_by_id(db, role_id)? {
+        Some(role) => {
+            SelfRole::delete(db, role_id)?;
+
+            let content = format!(
+                "Members can no longer pick {}, those who have it keep it.",
+                RoleId(role_id).mention()
+            );
+
+            respond_and_refresh(&ctx, db, &command, content, &[&role.role_group]).await
+        }
+        None => {
+            let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            data.flags(flags).content(format!(
+                                "Members already can't pick {}!",
+                                RoleId(role_id).mention()
+                            ))
+                        })
+                })
+                .await?;
+
+            Ok(())
+        }
+    }
+}
+
+async fn cmd_selfrole_exclusive(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let group = get_option(options, "group")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_lowercase())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let exclusive = get_option(options, "exclusive")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_bool())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    SelfRole::set_exclusive(db, &group, exclusive)?;
+
+    let content = if exclusive {
+        format!(
+            "Members can now hold only one role of `{}` at a time.",
+            group
+        )
+    } else {
+        format!("Members can now hold any number of roles of `{}`.", group)
+    };
+
+    respond_and_refresh(&ctx, db, &command, content, &[&group]).await
+}
+
+async fn cmd_selfrole_list(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let roles = SelfRole::get_all(db)?;
+
+    // Roles are ordered by group, so each group's roles are contiguous
+    let mut description = String::new();
+    let mut group = None;
+    for role in &roles {
+        if group != Some(&role.role_group) {
+            description.push_str(&format!(
+                "\n**__{}__**{}\n",
+                role.role_group,
+                if SelfRole::is_exclusive(db, &role.role_group)? {
+                    " (pick one)"
+                } else {
+                    ""
+                }
+            ));
+            group = Some(&role.role_group);
+        }
+
+        description.push_str(&format!(
+            "{} as **{}**, allowed by {}\n",
+            RoleId(role.role_id).mention(),
+            role.label,
+            UserId(role.actor_id).mention()
+        ));
+    }
+
+    if roles.is_empty() {
+        description.push_str("Members can't pick any roles yet.");
+    }
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags).embed(|embed| {
+                        embed
+                            .title("Self-assignable roles")
+                            .description(truncate(&description, 4096))
+                    })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Offers the roles of a group under a bot-authored message, or stops offering any.
+pub async fn cmd_botmsg_roles(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+) -> InteractionResult {
+    let options = &command
+        .data
+        .options
+        .get(0)
+        .ok_or(InteractionError::UnprocessableRequest)?
+        .options;
+
+    let message = get_option(options, "message")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let group = get_option(options, "group")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_lowercase());
+
+    let style = get_option(options, "style")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .unwrap_or(BotMessage::ROLES_BUTTONS);
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    let target = match BotMessage::get_by_name(db, message.trim())? {
+        Some(message) => match &group {
+            Some(group) => {
+                let role_menu = RoleMenu::new(db, group, style)?;
+                if role_menu.roles.is_empty() {
+                    Err(format!(
+                        "Members can't pick any roles of `{}`, allow some with `/selfrole allow`!",
+                        group
+                    ))
+                } else {
+                    Ok((message, Some(role_menu)))
+                }
+            }
+            None => Ok((message, None)),
+        },
+        None => Err(format!("No bot-authored message named `{}`!", message)),
+    };
+
+    let (message, role_menu) = match target {
+        Ok(target) => target,
+        Err(why) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| data.flags(flags).content(why))
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags))
+        })
+        .await?;
+
+    let channel_id = ChannelId(message.channel_id);
+    let msg_id = MessageId(message.message_id);
+
+    let draft = match channel_id.message(&ctx.http, msg_id).await {
+        Ok(msg) => MessageDraft::from_message(&msg),
+        Err(why) => {
+            command
+                .create_followup_message(&ctx.http, |followup| {
+                    followup.content(format!("Failed to get bot-authored message: {}", why))
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let rows = role_menu.as_ref().map_or(0, |role_menu| role_menu.rows());
+    if draft.components.len() + rows > MAX_ACTION_ROWS {
+        command
+            .create_followup_message(&ctx.http, |followup| {
+                followup.content(format!(
+                    "`{}` has too many rows of link buttons to fit the roles, a message can have \
+                    at most {} rows!",
+                    message.name, MAX_ACTION_ROWS
+                ))
+            })
+            .await?;
+
+        return Ok(());
+    }
+
+    BotMessage::set_role_menu(
+        db,
+        message.id,
+        group.as_deref(),
+        group.as_ref().map(|_| style),
+    )?;
+
+    let result = send_draft(
+        &ctx.http,
+        db,
+        channel_id,
+        Some(msg_id),
+        &draft,
+        role_menu.as_ref(),
+        MENTIONS_NONE,
+    )
+    .await;
+
+    command
+        .create_followup_message(&ctx.http, |followup| {
+            delivery_followup(followup, Some(msg_id), result)
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn button_self_role_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    role_id: &str,
+) -> InteractionResult {
+    let role_id = role_id
+        .parse::<u64>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    let mut member = interaction
+        .member
+        .clone()
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let content = match SelfRole::get_by_id(db, role_id)? {
+        Some(role) => {
+            let result = if member.roles.contains(&RoleId(role_id)) {
+                member
+                    .remove_role(&ctx.http, role_id)
+                    .await
+                    .map(|_| format!("Removed {} from you.", RoleId(role_id).mention()))
+            } else {
+                // Picking a role of an exclusive group swaps out any other role of the group
+                let mut replaced = Vec::new();
+                if SelfRole::is_exclusive(db, &role.role_group)? {
+                    for other in SelfRole::get_by_group(db, &role.role_group)? {
+                        if member.roles.contains(&RoleId(other.role_id)) {
+                            replaced.push(RoleId(other.role_id));
+                        }
+                    }
+                }
+
+                let mut result = Ok(());
+                for other in &replaced {
+                    result = result.and(member.remove_role(&ctx.http, *other).await);
+                }
+
+                match result {
+                    Ok(()) => member.add_role(&ctx.http, role_id).await.map(|_| {
+                        if replaced.is_empty() {
+                            format!("Gave you {}.", RoleId(role_id).mention())
+                        } else {
+                            format!(
+                                "Gave you {} in place of {}.",
+                                RoleId(role_id).mention(),
+                                mentions(&replaced)
+                            )
+                        }
+                    }),
+                    Err(why) => Err(why),
+                }
+            };
+
+            result.unwrap_or_else(role_change_failure)
+        }
+        None => "That role can no longer be picked here!".to_string(),
+    };
+
+    respond_ephemeral(&ctx.http, &interaction, content).await
+}
+
+pub async fn select_self_role_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    group: &str,
+) -> InteractionResult {
+    let mut member = interaction
+        .member
+        .clone()
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let roles = SelfRole::get_by_group(db, group)?;
+    let selected: Vec<RoleId> = interaction
+        .data
+        .values
+        .iter()
+        .filter_map(|value| value.parse::<u64>().ok())
+        .filter(|role_id| roles.iter().any(|role| role.role_id == *role_id))
+        .map(RoleId)
+        .collect();
+
+    if SelfRole::is_exclusive(db, group)? && selected.len() > 1 {
+        return respond_ephemeral(&ctx.http, &interaction, "Pick only one role!".to_string()).await;
+    }
+
+    // The member's roles of the group are made to match exactly what they selected
+    let (mut added, mut removed) = (Vec::new(), Vec::new());
+    let mut result = Ok(());
+    for role in &roles {
+        let role_id = RoleId(role.role_id);
+        let has = member.roles.contains(&role_id);
+
+        if selected.contains(&role_id) && !has {
+            result = result.and(member.add_role(&ctx.http, role_id).await);
+            added.push(role_id);
+        } else if !selected.contains(&role_id) && has {
+            result = result.and(member.remove_role(&ctx.http, role_id).await);
+            removed.push(role_id);
+        }
+    }
+
+    let content = match result {
+        Ok(()) if added.is_empty() && removed.is_empty() => "Your roles are unchanged.".to_string(),
+        Ok(()) => {
+            let mut changes = Vec::new();
+            if !added.is_empty() {
+                changes.push(format!("Gave you {}.", mentions(&added)));
+            }
+            if !removed.is_empty() {
+                changes.push(format!("Removed {} from you.", mentions(&removed)));
+            }
+            changes.join("\n")
+        }
+        Err(why) => role_change_failure(why),
+    };
+
+    respond_ephemeral(&ctx.http, &interaction, content).await
+}
+
+/// Responds to the admin and updates every message offering the roles of `groups`, since what
+/// they offer may have changed.
+async fn respond_and_refresh(
+    ctx: &Context,
+    db: &Database,
+    command: &ApplicationCommandInteraction,
+    content: String,
+    groups: &[&str],
+) -> InteractionResult {
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| data.flags(flags).content(content))
+        })
+        .await?;
+
+    for group in groups {
+        for message in BotMessage::get_by_role_group(db, group)? {
+            if let Err(why) = refresh_message(&ctx.http, db, &message).await {
+                println!(
+                    "Error when updating roles under bot message {}: {:?}",
+                    message.name, why
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Re-sends a bot message as it is, with its role menu rebuilt.
+async fn refresh_message(
+    http: &Http,
+    db: &Database,
+    message: &BotMessage,
+) -> Result<Message, InteractionError> {
+    let channel_id = ChannelId(message.channel_id);
+    let msg_id = MessageId(message.message_id);
+
+    let draft = MessageDraft::from_message(&channel_id.message(http, msg_id).await?);
+    let role_menu = RoleMenu::for_message(db, message)?;
+
+    Ok(send_draft(
+        http,
+        db,
+        channel_id,
+        Some(msg_id),
+        &draft,
+        role_menu.as_ref(),
+        MENTIONS_NONE,
+    )
+    .await?)
+}
+
+async fn respond_ephemeral(
+    http: &Http,
+    interaction: &MessageComponentInteraction,
+    content: String,
+) -> InteractionResult {
+    interaction
+        .create_interaction_response(http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .content(content)
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn mentions(role_ids: &[RoleId]) -> String {
+    role_ids
+        .iter()
+        .map(|role_id| role_id.mention().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn role_change_failure(why: serenity::Error) -> String {
+    println!("Error when changing self-assigned roles: {:?}", why);
+
+    // Usually the bot lacks Manage Roles, or the role is above the bot's own
+    "Couldn't change your roles, please let an admin know!".to_string()
+}
@@ -0,0 +1,411 @@
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    client::Context,
+    model::{
+        channel::Message,
+        id::{ChannelId, MessageId, RoleId, UserId},
+        interactions::{
+            application_command::ApplicationCommandInteraction,
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+    },
+    prelude::Mentionable,
+};
+
+use crate::{
+    db::{
+        models::{Challenge, Setting, Ticket},
+        Database,
+    },
+    get_option, response_flags, truncate, InteractionError, InteractionResult,
+};
+
+/// Followed by `:<ticket id>`
+pub const ID_BUTTON_TICKET_CLAIM: &str = "button_ticket_claim";
+/// Followed by `:<ticket id>`
+pub const ID_BUTTON_TICKET_CLOSE: &str = "button_ticket_close";
+
+pub async fn cmd_ticket(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    let description = get_option(&command.data.options, "description")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .ok_or(InteractionError::UnprocessableRequest)?;
+
+    let challenge = match get_option(&command.data.options, "challenge")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+    {
+        Some(challenge_id) => Challenge::get_by_id(db, challenge_id)?,
+        None => None,
+    };
+
+    // Tickets go in the ticket channel if there is one, otherwise wherever they were opened
+    let channel_id = match Setting::get_id(db, Setting::TICKET_CHANNEL)? {
+        Some(channel_id) => ChannelId(channel_id),
+        None => command.channel_id,
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                })
+        })
+        .await?;
+
+    let id = Ticket::create(
+        db,
+        command.user.id.0,
+        challenge.as_ref().map(|c| c.id),
+        description,
+    )?;
+
+    let thread = match channel_id
+        .create_private_thread(&ctx.http, |thread| {
+            thread
+                .name(truncate(&format!("Ticket #{}: {}", id, description), 100))
+                .auto_archive_duration(10080)
+        })
+        .await
+    {
+        Ok(thread) => thread,
+        Err(why) => {
+            // Nobody could answer a ticket without its thread, e.g. if opened inside a thread
+            Ticket::delete(db, id)?;
+
+            command
+                .create_followup_message(&ctx.http, |message| {
+                    message.content(format!("Failed to open a ticket thread: {}", why))
+                })
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    Ticket::set_thread_id(db, id, thread.id.0)?;
+
+    let ticket = Ticket::get_by_id(db, id)?.ok_or(InteractionError::UnprocessableRequest)?;
+
+    thread
+        .id
+        .add_thread_member(&ctx.http, command.user.id)
+        .await?;
+
+    // Mentioning the admin role brings its members into the private thread
+    thread
+        .id
+        .send_message(&ctx.http, |message| {
+            message
+                .content(format!(
+                    "{} opened a ticket, {} will be with you soon!",
+                    command.user.mention(),
+                    RoleId(admin_role_id).mention()
+                ))
+                .allowed_mentions(|mentions| {
+                    mentions
+                        .users(vec![command.user.id])
+                        .roles(vec![RoleId(admin_role_id)])
+                })
+                .embed(|embed| ticket_embed(embed, &ticket, challenge.as_ref()))
+                .components(|components| ticket_buttons(components, &ticket))
+        })
+        .await?;
+
+    command
+        .create_followup_message(&ctx.http, |message| {
+            message.content(format!(
+                "Opened ticket #{}, head over to {}",
+                id,
+                thread.id.mention()
+            ))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Lists tickets that haven't been closed yet.
+pub async fn cmd_tickets(
+    ctx: Context,
+    db: &Database,
+    command: ApplicationCommandInteraction,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !command
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let tickets = Ticket::get_unclosed(db)?;
+
+    let description = if tickets.is_empty() {
+        "There are no open tickets.".to_string()
+    } else {
+        tickets
+            .iter()
+            .map(|ticket| {
+                format!(
+                    "`#{}` {} by {} <t:{}:R>: {}{}",
+                    ticket.id,
+                    match ticket.thread_id {
+                        Some(thread_id) => ChannelId(thread_id).mention().to_string(),
+                        None => "(no thread)".to_string(),
+                    },
+                    UserId(ticket.user_id).mention(),
+                    ticket.created_at,
+                    truncate(&ticket.description, 50),
+                    match ticket.claimed_by {
+                        Some(admin_id) => format!(" (claimed by {})", UserId(admin_id).mention()),
+                        None => String::new(),
+                    }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let flags = response_flags(db, Setting::OUTCOME_ADMIN)?;
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.flags(flags)
+                        .embed(|embed| embed.title("Open tickets").description(description))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub async fn button_ticket_claim_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    ticket_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let ticket_id = ticket_id
+        .parse::<i64>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    Ticket::claim(db, ticket_id, interaction.user.id.0)?;
+
+    update_ticket_message(&ctx, db, &interaction, ticket_id).await
+}
+
+pub async fn button_ticket_close_response(
+    ctx: Context,
+    db: &Database,
+    interaction: MessageComponentInteraction,
+    ticket_id: &str,
+    admin_role_id: u64,
+) -> InteractionResult {
+    if !interaction
+        .member
+        .as_ref()
+        .ok_or(InteractionError::Permissions)?
+        .roles
+        .contains(&RoleId(admin_role_id))
+    {
+        return Err(InteractionError::Permissions);
+    }
+
+    let ticket_id = ticket_id
+        .parse::<i64>()
+        .map_err(|_| InteractionError::UnprocessableRequest)?;
+
+    let ticket = Ticket::get_by_id(db, ticket_id)?.ok_or(InteractionError::UnprocessableRequest)?;
+
+    let closed = Ticket::close(db, ticket_id)?;
+
+    update_ticket_message(&ctx, db, &interaction, ticket_id).await?;
+
+    if !closed {
+        return Ok(());
+    }
+
+    // Fetching the whole thread takes a while, so this is done after responding
+    let transcript = match ticket.thread_id {
+        Some(thread_id) => transcript(&ctx, ChannelId(thread_id)).await?,
+        None => String::new(),
+    };
+
+    Ticket::set_transcript(db, ticket_id, &transcript)?;
+
+    if let Some(channel_id) = Setting::get_id(db, Setting::ADMIN_CHANNEL)? {
+        ChannelId(channel_id)
+            .send_message(&ctx.http, |message| {
+                message
+                    .embed(|embed| {
+                        embed
+                            .title(format!("Ticket #{} closed", ticket.id))
+                            .description(truncate(&ticket.description, 4096))
+                            .field("Reporter", UserId(ticket.user_id).mention(), true)
+                            .field("Closed by", interaction.user.mention(), true)
+                    })
+                    .add_file((
+                        transcript.as_bytes(),
+                        format!("ticket-{}.txt", ticket.id).as_str(),
+                    ))
+            })
+            .await?;
+    }
+
+    if let Some(thread_id) = ticket.thread_id {
+        ChannelId(thread_id)
+            .edit_thread(&ctx.http, |thread| thread.archived(true).locked(true))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Updates the message a ticket's buttons are on to show its current status.
+async fn update_ticket_message(
+    ctx: &Context,
+    db: &Database,
+    interaction: &MessageComponentInteraction,
+    ticket_id: i64,
+) -> InteractionResult {
+    let ticket = Ticket::get_by_id(db, ticket_id)?.ok_or(InteractionError::UnprocessableRequest)?;
+    let challenge = match ticket.challenge_id {
+        Some(challenge_id) => Challenge::get_by_id(db, challenge_id)?,
+        None => None,
+    };
+
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.embed(|embed| ticket_embed(embed, &ticket, challenge.as_ref()))
+                        .components(|components| ticket_buttons(components, &ticket))
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Builds a plain text transcript of every message in a ticket's thread, oldest first.
+async fn transcript(ctx: &Context, thread_id: ChannelId) -> Result<String, InteractionError> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut before: Option<MessageId> = None;
+
+    loop {
+        let page = thread_id
+            .messages(&ctx.http, |retriever| {
+                if let Some(before) = before {
+                    retriever.before(before);
+                }
+                retriever.limit(100)
+            })
+            .await?;
+
+        let done = page.len() < 100;
+        before = page.last().map(|m| m.id);
+        messages.extend(page);
+
+        if done {
+            break;
+        }
+    }
+
+    Ok(messages
+        .iter()
+        .rev()
+        .map(|message| {
+            let mut line = format!(
+                "[{}] {}: {}",
+                message.timestamp,
+                message.author.tag(),
+                message.content
+            );
+
+            for attachment in &message.attachments {
+                line.push_str(&format!(" <{}>", attachment.url));
+            }
+
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn ticket_embed<'a>(
+    embed: &'a mut CreateEmbed,
+    ticket: &Ticket,
+    challenge: Option<&Challenge>,
+) -> &'a mut CreateEmbed {
+    let (status, colour) = match (ticket.status.as_str(), ticket.claimed_by) {
+        (Ticket::CLOSED, _) => ("Closed".to_string(), (128, 128, 128)),
+        (_, Some(admin_id)) => (
+            format!("Claimed by {}", UserId(admin_id).mention()),
+            (255, 165, 0),
+        ),
+        _ => ("Open".to_string(), (0, 255, 0)),
+    };
+
+    embed
+        .title(format!("Ticket #{}", ticket.id))
+        .colour(colour)
+        .description(truncate(&ticket.description, 4096))
+        .field("Reporter", UserId(ticket.user_id).mention(), true)
+        .field("Status", status, true);
+
+    if let Some(challenge) = challenge {
+        embed.field("Challenge", &challenge.name, true);
+    }
+
+    embed
+}
+
+fn ticket_buttons<'a>(
+    components: &'a mut CreateComponents,
+    ticket: &Ticket,
+) -> &'a mut CreateComponents {
+    components.create_action_row(|action_row| {
+        action_row
+            .create_button(|button| {
+                button
+                    .style(ButtonStyle::Primary)
+                    .label("Claim")
+                    .custom_id(format!("{}:{}", ID_BUTTON_TICKET_CLAIM, ticket.id))
+                    .disabled(ticket.status != Ticket::OPEN)
+            })
+            .create_button(|button| {
+                button
+                    .style(ButtonStyle::Danger)
+                    .label("Close")
+                    .custom_id(format!("{}:{}", ID_BUTTON_TICKET_CLOSE, ticket.id))
+                    .disabled(ticket.status == Ticket::CLOSED)
+            })
+    })
+}